@@ -0,0 +1,44 @@
+use std::{env, fs::read_to_string};
+
+use day_1::{mean, median, parse_elves, percentile, top_n};
+
+fn main() {
+    let input = read_to_string("input.txt").unwrap();
+    let elves = parse_elves(&input);
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let csv = args.iter().any(|arg| arg == "--csv");
+    let n = args
+        .iter()
+        .find_map(|arg| arg.parse().ok())
+        .unwrap_or(elves.len());
+    let top = top_n(&elves, n);
+
+    if csv {
+        println!("rank,elf,calories");
+        for (rank, elf) in top.iter().enumerate() {
+            println!("{},{},{}", rank + 1, elf.index + 1, elf.calories());
+        }
+        return;
+    }
+
+    for (rank, elf) in top.iter().enumerate() {
+        println!(
+            "{:>4}. elf {:<4} {}",
+            rank + 1,
+            elf.index + 1,
+            elf.calories()
+        );
+    }
+    println!();
+    println!("elves:  {}", elves.len());
+    if let (Some(mean), Some(median)) = (mean(&elves), median(&elves)) {
+        println!("mean:   {mean:.1}");
+        println!("median: {median:.1}");
+    }
+    for percent in [25.0, 75.0, 90.0, 99.0] {
+        if let Some(elf) = percentile(&elves, percent) {
+            let label = format!("p{percent}:");
+            println!("{label:<8}{} (elf {})", elf.calories(), elf.index + 1);
+        }
+    }
+}
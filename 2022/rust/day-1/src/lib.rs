@@ -1,22 +1,85 @@
-use std::cmp::Reverse;
+use std::{cmp::Reverse, collections::BinaryHeap};
 
-pub fn part_1(input: String) -> u32 {
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Elf {
+    pub index: usize,
+    pub items: Vec<u32>,
+}
+
+impl Elf {
+    pub fn calories(&self) -> u32 {
+        self.items.iter().sum()
+    }
+}
+
+pub fn parse_elves(input: &str) -> Vec<Elf> {
     input
+        .trim()
         .split("\n\n")
-        .map(|elf| elf.lines().map(|item| item.parse::<u32>().unwrap()).sum())
-        .max()
-        .expect("no elves found")
+        .enumerate()
+        .map(|(index, elf)| Elf {
+            index,
+            items: elf
+                .lines()
+                .map(|item| item.trim().parse::<u32>().unwrap())
+                .collect(),
+        })
+        .collect()
 }
 
-pub fn part_2(input: String) -> u32 {
-    let mut elves: Vec<u32> = input
-        .split("\n\n")
-        .map(|elf| elf.lines().map(|item| item.parse::<u32>().unwrap()).sum())
-        .collect();
+pub fn top_n(elves: &[Elf], n: usize) -> Vec<&Elf> {
+    let mut heap = BinaryHeap::with_capacity(n.min(elves.len()) + 1);
+    for (position, elf) in elves.iter().enumerate() {
+        heap.push(Reverse((elf.calories(), Reverse(position))));
+        if heap.len() > n {
+            heap.pop();
+        }
+    }
+    heap.into_sorted_vec()
+        .into_iter()
+        .map(|Reverse((_, Reverse(position)))| &elves[position])
+        .collect()
+}
+
+pub fn mean(elves: &[Elf]) -> Option<f64> {
+    let total = elves.iter().map(|elf| elf.calories() as f64).sum::<f64>();
+    (!elves.is_empty()).then(|| total / elves.len() as f64)
+}
+
+pub fn median(elves: &[Elf]) -> Option<f64> {
+    let mut calories = elves.iter().map(Elf::calories).collect::<Vec<_>>();
+    calories.sort_unstable();
+    let middle = calories.len() / 2;
+    match calories.len() {
+        0 => None,
+        len if len % 2 == 1 => Some(calories[middle] as f64),
+        _ => Some((calories[middle - 1] as f64 + calories[middle] as f64) / 2.0),
+    }
+}
+
+pub fn percentile(elves: &[Elf], percent: f64) -> Option<&Elf> {
+    if elves.is_empty() {
+        return None;
+    }
+    let mut ranked = elves.iter().collect::<Vec<_>>();
+    let rank = (percent.clamp(0.0, 100.0) / 100.0 * ranked.len() as f64).ceil() as usize;
+    let (_, elf, _) =
+        ranked.select_nth_unstable_by_key(rank.max(1) - 1, |elf| (elf.calories(), elf.index));
+    Some(*elf)
+}
 
-    elves.sort_by_key(|elf| Reverse(*elf));
+pub fn part_1(input: String) -> u32 {
+    top_n(&parse_elves(&input), 1)
+        .first()
+        .expect("no elves found")
+        .calories()
+}
 
-    elves.iter().take(3).sum()
+pub fn part_2(input: String) -> u32 {
+    top_n(&parse_elves(&input), 3)
+        .iter()
+        .map(|elf| elf.calories())
+        .sum()
 }
 
 #[cfg(test)]
@@ -46,4 +109,39 @@ mod tests {
     fn top_3_calorie() {
         assert_eq!(part_2(INPUT.to_string()), 45000);
     }
+
+    #[test]
+    fn ranked_elves() {
+        let elves = parse_elves(INPUT);
+        assert_eq!(elves.len(), 5);
+        let top = top_n(&elves, 3);
+        assert_eq!(
+            top.iter()
+                .map(|elf| (elf.index, elf.calories()))
+                .collect::<Vec<_>>(),
+            [(3, 24000), (2, 11000), (4, 10000)]
+        );
+        assert_eq!(top_n(&elves, 10).len(), 5);
+        assert!(top_n(&elves, 0).is_empty());
+        assert_eq!(top_n(&elves, 1 << 40).len(), 5);
+        assert_eq!(top_n(&elves, usize::MAX).len(), 5);
+
+        let elves = parse_elves("300\n\n100\n200\n\n50\n\n300");
+        let top = top_n(&elves, 2);
+        assert_eq!(top.iter().map(|elf| elf.index).collect::<Vec<_>>(), [0, 1]);
+    }
+
+    #[test]
+    fn calorie_statistics() {
+        let elves = parse_elves(INPUT);
+        assert_eq!(mean(&elves), Some(11000.0));
+        assert_eq!(median(&elves), Some(10000.0));
+        assert_eq!(median(&elves[..4]), Some(8500.0));
+        assert_eq!(percentile(&elves, 0.0).map(|elf| elf.index), Some(1));
+        assert_eq!(percentile(&elves, 50.0).map(|elf| elf.index), Some(4));
+        assert_eq!(percentile(&elves, 90.0).map(|elf| elf.index), Some(3));
+        assert_eq!(mean(&[]), None);
+        assert_eq!(median(&[]), None);
+        assert_eq!(percentile(&[], 50.0), None);
+    }
 }
@@ -0,0 +1,33 @@
+use std::fs::read_to_string;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use day_12::{shortest_path, MoveRules, Search};
+
+fn path_search(c: &mut Criterion) {
+    let input = read_to_string("input.txt").unwrap();
+    let mut group = c.benchmark_group("path_search");
+    group.sample_size(10);
+    for rules in [
+        MoveRules::default(),
+        MoveRules {
+            diagonal: true,
+            ..MoveRules::default()
+        },
+    ] {
+        let name = if rules.diagonal {
+            "diagonal"
+        } else {
+            "default"
+        };
+        group.bench_function(format!("dijkstra/{name}"), |b| {
+            b.iter(|| shortest_path(&input, &rules, Search::Dijkstra))
+        });
+        group.bench_function(format!("astar/{name}"), |b| {
+            b.iter(|| shortest_path(&input, &rules, Search::AStar))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, path_search);
+criterion_main!(benches);
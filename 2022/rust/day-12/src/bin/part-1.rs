@@ -1,8 +1,14 @@
-use std::fs::read_to_string;
+use std::{fs::read_to_string, process::exit};
 
 use day_12::part_1;
 
 fn main() {
     let input = read_to_string("input.txt").unwrap();
-    println!("{}", part_1(&input));
+    match part_1(&input) {
+        Ok(steps) => println!("{steps}"),
+        Err(err) => {
+            eprintln!("{err}");
+            exit(1);
+        }
+    }
 }
@@ -1,13 +1,12 @@
-use std::{convert::Infallible, fs::File, io::Write, str::FromStr};
+use std::{error::Error, fmt, str::FromStr};
 
 use grid::Grid;
 use petgraph::{
-    algo::dijkstra,
-    dot::{Config, Dot},
-    prelude::{DiGraphMap, UnGraphMap},
+    algo::{astar, dijkstra},
+    prelude::DiGraphMap,
 };
 
-#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug)]
 struct Point {
     row: usize,
     col: usize,
@@ -34,101 +33,221 @@ impl PossibleMove {
     }
 }
 
+#[derive(Clone, Copy, Debug)]
+pub struct MoveRules {
+    pub max_climb: u8,
+    pub max_descent: Option<u8>,
+    pub diagonal: bool,
+    pub step_cost: usize,
+    pub climb_cost: usize,
+    pub descent_cost: usize,
+}
+
+impl Default for MoveRules {
+    fn default() -> Self {
+        MoveRules {
+            max_climb: 1,
+            max_descent: None,
+            diagonal: false,
+            step_cost: 1,
+            climb_cost: 0,
+            descent_cost: 0,
+        }
+    }
+}
+
+impl MoveRules {
+    fn cost(&self, from: u8, to: u8) -> Option<usize> {
+        if to > from && to - from > self.max_climb {
+            return None;
+        }
+        if let Some(max_descent) = self.max_descent {
+            if from > to && from - to > max_descent {
+                return None;
+            }
+        }
+        let change_cost = if to > from {
+            (to - from) as usize * self.climb_cost
+        } else {
+            (from - to) as usize * self.descent_cost
+        };
+        Some(self.step_cost + change_cost)
+    }
+
+    fn offsets(&self) -> Vec<(isize, isize)> {
+        let mut offsets = vec![(0, -1), (0, 1), (-1, 0), (1, 0)];
+        if self.diagonal {
+            offsets.extend([(-1, -1), (-1, 1), (1, -1), (1, 1)]);
+        }
+        offsets
+    }
+
+    fn heuristic(&self, from: Point, to: Point) -> usize {
+        let rows = from.row.abs_diff(to.row);
+        let cols = from.col.abs_diff(to.col);
+        let steps = if self.diagonal {
+            rows.max(cols)
+        } else {
+            rows + cols
+        };
+        steps * self.step_cost
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Search {
+    Dijkstra,
+    AStar,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MapError {
+    Empty,
+    MissingMarker(char),
+    DuplicateMarker(char),
+    UnevenRow {
+        row: usize,
+        expected: usize,
+        found: usize,
+    },
+    InvalidChar {
+        row: usize,
+        col: usize,
+        char: char,
+    },
+    Unreachable,
+}
+
+impl fmt::Display for MapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapError::Empty => write!(f, "map is empty"),
+            MapError::MissingMarker(marker) => write!(f, "map has no '{marker}' marker"),
+            MapError::DuplicateMarker(marker) => {
+                write!(f, "map has more than one '{marker}' marker")
+            }
+            MapError::UnevenRow {
+                row,
+                expected,
+                found,
+            } => write!(f, "row {row} has {found} columns, expected {expected}"),
+            MapError::InvalidChar { row, col, char } => {
+                write!(f, "invalid character {char:?} at row {row}, column {col}")
+            }
+            MapError::Unreachable => write!(f, "'E' is not reachable from 'S'"),
+        }
+    }
+}
+
+impl Error for MapError {}
+
 struct MapSetup {
     start: Point,
     end: Point,
-    line_width: usize,
+    grid: Grid<u8>,
 }
 
 impl FromStr for MapSetup {
-    type Err = Infallible;
+    type Err = MapError;
 
-    fn from_str(s: &str) -> Result<Self, Infallible> {
+    fn from_str(s: &str) -> Result<Self, MapError> {
+        let line_width = s.lines().next().ok_or(MapError::Empty)?.chars().count();
+        if line_width == 0 {
+            return Err(MapError::Empty);
+        }
+        let mut start = None;
+        let mut end = None;
+        let mut heights = vec![];
+        for (row, line) in s.lines().enumerate() {
+            let found = line.chars().count();
+            if found != line_width {
+                return Err(MapError::UnevenRow {
+                    row,
+                    expected: line_width,
+                    found,
+                });
+            }
+            for (col, char) in line.chars().enumerate() {
+                let height = match char {
+                    'S' => {
+                        if start.replace(Point { row, col }).is_some() {
+                            return Err(MapError::DuplicateMarker('S'));
+                        }
+                        b'a'
+                    }
+                    'E' => {
+                        if end.replace(Point { row, col }).is_some() {
+                            return Err(MapError::DuplicateMarker('E'));
+                        }
+                        b'z'
+                    }
+                    'a'..='z' => char as u8,
+                    char => return Err(MapError::InvalidChar { row, col, char }),
+                };
+                heights.push(height);
+            }
+        }
         Ok(MapSetup {
-            start: get_coord(s, 'S'),
-            end: get_coord(s, 'E'),
-            line_width: s.lines().next().unwrap().len(),
+            start: start.ok_or(MapError::MissingMarker('S'))?,
+            end: end.ok_or(MapError::MissingMarker('E'))?,
+            grid: Grid::from_vec(heights, line_width),
         })
     }
 }
 
-fn get_coord(input: &str, point: char) -> Point {
-    let (row, point_line) = input
-        .lines()
-        .enumerate()
-        .find(|(_, line)| line.contains(point))
-        .unwrap();
-    let col = point_line.find(|char| char == point).unwrap();
-    Point { col, row }
-}
-
-pub fn part_1(input: &str) -> usize {
-    let setup = input.parse::<MapSetup>().unwrap();
-    let grid = Grid::from_vec(
-        input
-            .lines()
-            .flat_map(|line| {
-                line.chars().map(|char| {
-                    let mut char = char;
-                    if char == 'S' {
-                        char = 'a';
-                    }
-                    if char == 'E' {
-                        char = 'z';
-                    }
-                    char as u8
-                })
-            })
-            .collect::<Vec<u8>>(),
-        setup.line_width,
-    );
-    let mut edges = vec![];
+fn map_graph(grid: &Grid<u8>, rules: &MoveRules) -> DiGraphMap<Point, usize> {
+    let mut graph = DiGraphMap::new();
+    let offsets = rules.offsets();
     for row in 0..grid.rows() {
         for col in 0..grid.cols() {
-            let current_node = grid.get(row, col).unwrap();
-            [
-                PossibleMove {
-                    col: col as isize - 1,
-                    row: row as isize,
-                },
-                PossibleMove {
-                    col: col as isize + 1,
-                    row: row as isize,
-                },
-                PossibleMove {
-                    col: col as isize,
-                    row: row as isize - 1,
-                },
-                PossibleMove {
-                    col: col as isize,
-                    row: row as isize + 1,
-                },
-            ]
-            .iter()
-            .filter_map(|possible_move| {
-                possible_move.try_into_point(grid.rows() - 1, grid.cols() - 1)
-            })
-            .filter_map(|point| {
-                let point_node = grid.get(point.row, point.col)?;
-                if current_node + 1 >= *point_node {
-                    Some(((col, row), (point.col, point.row)))
-                } else {
-                    None
-                }
-            })
-            .for_each(|link| {
-                edges.push(link);
-            });
+            let current_point = Point { row, col };
+            let current_node = *grid.get(row, col).unwrap();
+            graph.add_node(current_point);
+            offsets
+                .iter()
+                .map(|(row_offset, col_offset)| PossibleMove {
+                    row: row as isize + row_offset,
+                    col: col as isize + col_offset,
+                })
+                .filter_map(|possible_move| {
+                    possible_move.try_into_point(grid.rows() - 1, grid.cols() - 1)
+                })
+                .filter_map(|point| {
+                    let point_node = *grid.get(point.row, point.col)?;
+                    rules
+                        .cost(current_node, point_node)
+                        .map(|cost| (point, cost))
+                })
+                .for_each(|(point, cost)| {
+                    graph.add_edge(current_point, point, cost);
+                });
+        }
+    }
+    graph
+}
+
+pub fn shortest_path(input: &str, rules: &MoveRules, search: Search) -> Result<usize, MapError> {
+    let setup = input.parse::<MapSetup>()?;
+    let graph = map_graph(&setup.grid, rules);
+    match search {
+        Search::Dijkstra => {
+            let path = dijkstra(&graph, setup.start, Some(setup.end), |(_, _, cost)| *cost);
+            path.get(&setup.end).copied()
         }
+        Search::AStar => astar(
+            &graph,
+            setup.start,
+            |point| point == setup.end,
+            |(_, _, cost)| *cost,
+            |point| rules.heuristic(point, setup.end),
+        )
+        .map(|(cost, _)| cost),
     }
-    let gr = DiGraphMap::<_, ()>::from_edges(&edges);
-    let path = dijkstra(
-        &gr,
-        (setup.start.col, setup.start.row),
-        Some((setup.end.col, setup.end.row)),
-        |_| 1,
-    );
-    path[&(setup.end.col, setup.end.row)]
+    .ok_or(MapError::Unreachable)
+}
+
+pub fn part_1(input: &str) -> Result<usize, MapError> {
+    shortest_path(input, &MoveRules::default(), Search::Dijkstra)
 }
 
 #[cfg(test)]
@@ -142,6 +261,60 @@ abdefghi";
 
     #[test]
     fn shortest_path() {
-        assert_eq!(part_1(INPUT), 31);
+        assert_eq!(part_1(INPUT), Ok(31));
+    }
+
+    #[test]
+    fn astar_matches_dijkstra() {
+        let rules = MoveRules::default();
+        assert_eq!(super::shortest_path(INPUT, &rules, Search::AStar), Ok(31));
+
+        let rules = MoveRules {
+            diagonal: true,
+            climb_cost: 2,
+            ..MoveRules::default()
+        };
+        assert_eq!(
+            super::shortest_path(INPUT, &rules, Search::AStar),
+            super::shortest_path(INPUT, &rules, Search::Dijkstra)
+        );
+    }
+
+    #[test]
+    fn malformed_maps() {
+        assert_eq!(part_1(""), Err(MapError::Empty));
+        assert_eq!(part_1("Sab\nabc"), Err(MapError::MissingMarker('E')));
+        assert_eq!(
+            part_1("Sab\nabcE"),
+            Err(MapError::UnevenRow {
+                row: 1,
+                expected: 3,
+                found: 4
+            })
+        );
+        assert_eq!(
+            part_1("Sa1\nabE"),
+            Err(MapError::InvalidChar {
+                row: 0,
+                col: 2,
+                char: '1'
+            })
+        );
+        assert_eq!(
+            part_1("Sab\naéE"),
+            Err(MapError::InvalidChar {
+                row: 1,
+                col: 1,
+                char: 'é'
+            })
+        );
+        assert_eq!(part_1("SaS\nabE"), Err(MapError::DuplicateMarker('S')));
+        assert_eq!(part_1("SaE\nabE"), Err(MapError::DuplicateMarker('E')));
+        assert_eq!(part_1("SazE"), Err(MapError::Unreachable));
+        let rules = MoveRules::default();
+        assert_eq!(
+            super::shortest_path("SazE", &rules, Search::AStar),
+            Err(MapError::Unreachable)
+        );
     }
 }
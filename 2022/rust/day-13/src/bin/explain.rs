@@ -0,0 +1,11 @@
+use std::fs::read_to_string;
+
+use day_13::explain_pairs;
+
+fn main() {
+    let input = read_to_string("input.txt").unwrap();
+    for (idx, explanation) in explain_pairs(&input).iter().enumerate() {
+        println!("== Pair {} ==", idx + 1);
+        println!("{explanation}");
+    }
+}
@@ -0,0 +1,29 @@
+use std::{env, fs::read_to_string, process::exit};
+
+use day_13::{parse_packets, sort_with_dividers, Packet};
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let path = args.next().unwrap_or_else(|| "input.txt".to_string());
+    let input = read_to_string(&path).unwrap();
+    let result = parse_packets(&input).and_then(|packets| {
+        let dividers = args
+            .map(|divider| divider.parse::<Packet>())
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok((sort_with_dividers(packets, &dividers), dividers))
+    });
+    match result {
+        Ok((sorted, dividers)) => {
+            for packet in &sorted.packets {
+                println!("{packet}");
+            }
+            for (divider, idx) in dividers.iter().zip(&sorted.divider_indices) {
+                eprintln!("{divider}: {idx}");
+            }
+        }
+        Err(err) => {
+            eprintln!("{err}");
+            exit(1);
+        }
+    }
+}
@@ -1,166 +1,323 @@
+use std::{cmp::Ordering, error::Error, fmt, slice, str::FromStr};
+
 use nom::{
     branch::alt,
     bytes::complete::tag,
-    character::complete::{self, newline},
+    character::complete::{digit1, newline},
+    combinator::all_consuming,
     multi::{separated_list0, separated_list1},
     sequence::{delimited, separated_pair},
     IResult, Parser,
 };
+use num_bigint::BigUint;
+use serde_json::Value;
+
+#[derive(Debug, Clone)]
+pub enum Packet {
+    Number(BigUint),
+    List(Vec<Packet>),
+}
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
-enum SignalItem {
-    Number(u8),
-    List(Vec<SignalItem>),
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PacketError {
+    InvalidSyntax(String),
+    InvalidValue(Value),
 }
 
-impl SignalItem {
-    fn get(&self) -> Vec<u8> {
+impl fmt::Display for PacketError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            SignalItem::Number(value) => vec![*value],
-            SignalItem::List(list) => {
-                if list.len() == 0 {
-                    vec![0]
-                } else {
-                    list.iter().flat_map(|item| item.get()).collect::<Vec<_>>()
-                }
+            PacketError::InvalidSyntax(input) => write!(f, "invalid packet syntax: {input}"),
+            PacketError::InvalidValue(value) => {
+                write!(f, "{value} is not a non-negative integer or array")
             }
         }
     }
 }
 
-#[derive(Debug)]
-struct FlatSignal {
-    items: Vec<u8>,
+impl Error for PacketError {}
+
+fn compare_lists(left: &[Packet], right: &[Packet]) -> Ordering {
+    left.iter()
+        .zip(right)
+        .map(|(left, right)| left.cmp(right))
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or_else(|| left.len().cmp(&right.len()))
 }
 
-impl From<&SignalPart> for FlatSignal {
-    fn from(signal_part: &SignalPart) -> Self {
-        Self {
-            items: signal_part
-                .items
-                .iter()
-                .flat_map(|item| item.get())
-                .collect::<Vec<_>>(),
+impl Ord for Packet {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Packet::Number(left), Packet::Number(right)) => left.cmp(right),
+            (Packet::List(left), Packet::List(right)) => compare_lists(left, right),
+            (Packet::Number(_), Packet::List(right)) => compare_lists(slice::from_ref(self), right),
+            (Packet::List(left), Packet::Number(_)) => compare_lists(left, slice::from_ref(other)),
         }
     }
 }
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
-struct SignalPart {
-    items: Vec<SignalItem>,
+impl PartialOrd for Packet {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
 }
 
-#[derive(Debug)]
-struct Pair {
-    left: SignalPart,
-    right: SignalPart,
-}
-
-fn nested_list_parser(input: &str) -> IResult<&str, SignalItem> {
-    let (input, item) = delimited(
-        tag("["),
-        separated_list0(
-            tag(","),
-            alt((
-                complete::u8.map(|number| SignalItem::Number(number)),
-                nested_list_parser,
-            )),
-        )
-        .map(|list| SignalItem::List(list)),
-        tag("]"),
-    )(input)?;
-    Ok((input, item))
+impl PartialEq for Packet {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
 }
 
-fn list_parser(input: &str) -> IResult<&str, SignalPart> {
-    let (input, _) = tag("[")(input)?;
-    if input.chars().next().unwrap() == ']' {
-        let (input, _) = tag("]")(input)?;
-        return Ok((input, SignalPart { items: vec![] }));
+impl Eq for Packet {}
+
+impl fmt::Display for Packet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Packet::Number(number) => write!(f, "{number}"),
+            Packet::List(list) => {
+                write!(f, "[")?;
+                for (idx, packet) in list.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{packet}")?;
+                }
+                write!(f, "]")
+            }
+        }
     }
-    let (input, items) = separated_list1(
-        tag(","),
-        alt((
-            complete::u8.map(|d| SignalItem::Number(d)),
-            nested_list_parser,
-        )),
-    )(input)?;
-    let (input, _) = tag("]")(input)?;
-    Ok((input, SignalPart { items }))
 }
 
-fn pair_parser(input: &str) -> IResult<&str, Pair> {
-    let (input, (left, right)) = separated_pair(list_parser, newline, list_parser)(input)?;
-    Ok((input, Pair { left, right }))
+impl FromStr for Packet {
+    type Err = PacketError;
+
+    fn from_str(s: &str) -> Result<Self, PacketError> {
+        all_consuming(packet_parser)(s.trim())
+            .map(|(_, packet)| packet)
+            .map_err(|_| PacketError::InvalidSyntax(s.to_string()))
+    }
 }
 
-fn input_parser(input: &str) -> Vec<Pair> {
-    separated_list1(tag("\n\n"), pair_parser)(input).unwrap().1
+impl From<&Packet> for Value {
+    fn from(packet: &Packet) -> Self {
+        match packet {
+            Packet::Number(number) => Value::Number(number.to_string().parse().unwrap()),
+            Packet::List(list) => Value::Array(list.iter().map(Value::from).collect()),
+        }
+    }
 }
 
-fn is_number_equal(left: &u8, right: &u8) -> Option<bool> {
-    if left == right {
-        None
-    } else {
-        Some(left < right)
+impl TryFrom<&Value> for Packet {
+    type Error = PacketError;
+
+    fn try_from(value: &Value) -> Result<Self, PacketError> {
+        match value {
+            Value::Number(number) => number
+                .to_string()
+                .parse::<BigUint>()
+                .map(Packet::Number)
+                .map_err(|_| PacketError::InvalidValue(value.clone())),
+            Value::Array(list) => list
+                .iter()
+                .map(Packet::try_from)
+                .collect::<Result<_, _>>()
+                .map(Packet::List),
+            _ => Err(PacketError::InvalidValue(value.clone())),
+        }
     }
 }
 
-fn is_ordered(left: Vec<&SignalItem>, right: Vec<&SignalItem>, is_inner: bool) -> Option<bool> {
-    let mut right_items = right.iter();
-    for left_item in &left {
-        match right_items.next() {
-            Some(right_item) => match (left_item, right_item) {
-                (SignalItem::Number(left_number), SignalItem::Number(right_number)) => {
-                    if let Some(equal) = is_number_equal(left_number, right_number) {
-                        return Some(equal);
-                    } else {
-                        continue;
-                    }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Side::Left => write!(f, "left"),
+            Side::Right => write!(f, "right"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    Smaller(Side),
+    RanOut(Side),
+}
+
+impl fmt::Display for Decision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (side, reason) = match self {
+            Decision::Smaller(side) => (side, "is smaller"),
+            Decision::RanOut(side) => (side, "ran out of items"),
+        };
+        let (side, order) = match side {
+            Side::Left => ("Left", "in the right order"),
+            Side::Right => ("Right", "not in the right order"),
+        };
+        write!(f, "{side} side {reason}, so inputs are {order}")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step {
+    Compare {
+        depth: usize,
+        left: Packet,
+        right: Packet,
+    },
+    Promote {
+        depth: usize,
+        path: Vec<usize>,
+        side: Side,
+        number: BigUint,
+    },
+    Decide {
+        depth: usize,
+        decision: Decision,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Explanation {
+    pub ordering: Ordering,
+    pub path: Vec<usize>,
+    pub decision: Option<Decision>,
+    pub steps: Vec<Step>,
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for step in &self.steps {
+            match step {
+                Step::Compare { depth, left, right } => {
+                    writeln!(f, "{}- Compare {left} vs {right}", "  ".repeat(*depth))?
                 }
-                (SignalItem::Number(_), SignalItem::List(right_list)) => {
-                    if let Some(equal) = is_ordered(
-                        vec![left_item],
-                        right_list.iter().collect::<Vec<&SignalItem>>(),
-                        true,
-                    ) {
-                        return Some(equal);
-                    } else {
-                        continue;
-                    }
+                Step::Promote {
+                    depth,
+                    side,
+                    number,
+                    ..
+                } => writeln!(
+                    f,
+                    "{}- Mixed types; convert {side} to [{number}] and retry comparison",
+                    "  ".repeat(*depth)
+                )?,
+                Step::Decide { depth, decision } => {
+                    writeln!(f, "{}- {decision}", "  ".repeat(*depth))?
                 }
-                (SignalItem::List(left_list), SignalItem::Number(_)) => {
-                    if let Some(equal) = is_ordered(
-                        left_list.iter().collect::<Vec<&SignalItem>>(),
-                        vec![right_item],
-                        true,
-                    ) {
-                        return Some(equal);
-                    } else {
-                        continue;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct Explainer {
+    path: Vec<usize>,
+    steps: Vec<Step>,
+}
+
+impl Explainer {
+    fn decide(&mut self, depth: usize, decision: Decision) -> Option<Decision> {
+        self.steps.push(Step::Decide { depth, decision });
+        Some(decision)
+    }
+
+    fn compare(&mut self, left: &Packet, right: &Packet, depth: usize) -> Option<Decision> {
+        self.steps.push(Step::Compare {
+            depth,
+            left: left.clone(),
+            right: right.clone(),
+        });
+        match (left, right) {
+            (Packet::Number(left), Packet::Number(right)) => match left.cmp(right) {
+                Ordering::Less => self.decide(depth + 1, Decision::Smaller(Side::Left)),
+                Ordering::Greater => self.decide(depth + 1, Decision::Smaller(Side::Right)),
+                Ordering::Equal => None,
+            },
+            (Packet::List(left), Packet::List(right)) => {
+                for (idx, (left, right)) in left.iter().zip(right).enumerate() {
+                    self.path.push(idx);
+                    if let Some(decision) = self.compare(left, right, depth + 1) {
+                        return Some(decision);
                     }
+                    self.path.pop();
                 }
-                (SignalItem::List(left_list), SignalItem::List(right_list)) => {
-                    if let Some(equal) = is_ordered(
-                        left_list.iter().collect::<Vec<&SignalItem>>(),
-                        right_list.iter().collect::<Vec<&SignalItem>>(),
-                        true,
-                    ) {
-                        return Some(equal);
-                    } else {
-                        continue;
+                self.path.push(left.len().min(right.len()));
+                match left.len().cmp(&right.len()) {
+                    Ordering::Less => self.decide(depth + 1, Decision::RanOut(Side::Left)),
+                    Ordering::Greater => self.decide(depth + 1, Decision::RanOut(Side::Right)),
+                    Ordering::Equal => {
+                        self.path.pop();
+                        None
                     }
                 }
-            },
-            None => return Some(false),
+            }
+            (Packet::Number(number), Packet::List(_)) => {
+                self.promote(depth + 1, Side::Left, number);
+                self.compare(&Packet::List(vec![left.clone()]), right, depth + 1)
+            }
+            (Packet::List(_), Packet::Number(number)) => {
+                self.promote(depth + 1, Side::Right, number);
+                self.compare(left, &Packet::List(vec![right.clone()]), depth + 1)
+            }
         }
     }
 
-    if is_inner && left.len() == right.len() {
-        return None;
+    fn promote(&mut self, depth: usize, side: Side, number: &BigUint) {
+        self.steps.push(Step::Promote {
+            depth,
+            path: self.path.clone(),
+            side,
+            number: number.clone(),
+        });
     }
+}
 
-    Some(left.len() < right.len())
+impl Packet {
+    pub fn explain(&self, other: &Packet) -> Explanation {
+        let mut explainer = Explainer::default();
+        let decision = explainer.compare(self, other, 0);
+        let ordering = match decision {
+            Some(Decision::Smaller(Side::Left) | Decision::RanOut(Side::Left)) => Ordering::Less,
+            Some(Decision::Smaller(Side::Right) | Decision::RanOut(Side::Right)) => {
+                Ordering::Greater
+            }
+            None => Ordering::Equal,
+        };
+        Explanation {
+            ordering,
+            path: explainer.path,
+            decision,
+            steps: explainer.steps,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Pair {
+    left: Packet,
+    right: Packet,
+}
+
+fn packet_parser(input: &str) -> IResult<&str, Packet> {
+    alt((
+        digit1.map(|digits: &str| Packet::Number(digits.parse().unwrap())),
+        delimited(tag("["), separated_list0(tag(","), packet_parser), tag("]")).map(Packet::List),
+    ))(input)
+}
+
+fn pair_parser(input: &str) -> IResult<&str, Pair> {
+    let (input, (left, right)) = separated_pair(packet_parser, newline, packet_parser)(input)?;
+    Ok((input, Pair { left, right }))
+}
+
+fn input_parser(input: &str) -> Vec<Pair> {
+    separated_list1(tag("\n\n"), pair_parser)(input).unwrap().1
 }
 
 pub fn part_1(input: &str) -> usize {
@@ -168,59 +325,76 @@ pub fn part_1(input: &str) -> usize {
     pairs
         .iter()
         .enumerate()
-        .filter_map(|(idx, pair)| {
-            let left = pair.left.items.iter().collect::<Vec<&SignalItem>>();
-            let right = pair.right.items.iter().collect::<Vec<&SignalItem>>();
-            if is_ordered(left, right, false).unwrap() {
-                Some(idx + 1)
-            } else {
-                None
-            }
-        })
-        .inspect(|idx| {
-            dbg!(idx);
-        })
+        .filter(|(_, pair)| pair.left < pair.right)
+        .map(|(idx, _)| idx + 1)
         .sum()
 }
 
-pub fn part_2(input: &str) -> usize {
-    let mut pairs = input_parser(input);
-    pairs.push(Pair {
-        left: SignalPart {
-            items: vec![SignalItem::Number(2)],
-        },
-        right: SignalPart {
-            items: vec![SignalItem::Number(6)],
-        },
-    });
-    let mut signals = pairs
+pub fn explain_pairs(input: &str) -> Vec<Explanation> {
+    input_parser(input)
         .iter()
-        .flat_map(|pair| [&pair.left, &pair.right])
-        .map(|signal| FlatSignal::from(signal).items)
+        .map(|pair| pair.left.explain(&pair.right))
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SortedPackets {
+    pub packets: Vec<Packet>,
+    pub divider_indices: Vec<usize>,
+}
+
+pub fn sort_with_dividers(
+    packets: impl IntoIterator<Item = Packet>,
+    dividers: &[Packet],
+) -> SortedPackets {
+    let mut tagged = packets
+        .into_iter()
+        .map(|packet| (packet, None))
+        .chain(
+            dividers
+                .iter()
+                .enumerate()
+                .map(|(idx, divider)| (divider.clone(), Some(idx))),
+        )
         .collect::<Vec<_>>();
-    signals.sort();
-    // dbg!(&signals);
-    signals
+    tagged.sort_by(|(left, _), (right, _)| left.cmp(right));
+
+    let mut divider_indices = vec![0; dividers.len()];
+    for (idx, (_, divider)) in tagged.iter().enumerate() {
+        if let Some(divider) = divider {
+            divider_indices[*divider] = idx + 1;
+        }
+    }
+    SortedPackets {
+        packets: tagged.into_iter().map(|(packet, _)| packet).collect(),
+        divider_indices,
+    }
+}
+
+pub fn parse_packets(input: &str) -> Result<Vec<Packet>, PacketError> {
+    input
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(str::parse)
+        .collect()
+}
+
+pub fn part_2(input: &str) -> usize {
+    let dividers = ["[[2]]", "[[6]]"].map(|divider| divider.parse::<Packet>().unwrap());
+    let packets = input_parser(input)
+        .into_iter()
+        .flat_map(|pair| [pair.left, pair.right]);
+    sort_with_dividers(packets, &dividers)
+        .divider_indices
         .iter()
-        .enumerate()
-        .filter_map(|(idx, signal)| {
-            if signal.len() == 1 {
-                if signal[0] == 2 || signal[0] == 6 {
-                    dbg!(signal);
-                    return Some(idx + 1);
-                }
-            }
-            None
-        })
-        .inspect(|item| {
-            dbg!(item);
-        })
         .product()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
+    use std::collections::BTreeSet;
 
     const INPUT: &str = "[1,1,3,1,1]
 [1,1,5,1,1]
@@ -253,6 +427,107 @@ mod tests {
 
     #[test]
     fn decoder_key() {
-        assert_eq!(part_2(INPUT), 141);
+        assert_eq!(part_2(INPUT), 140);
+    }
+
+    #[test]
+    fn packet_ordering() {
+        let packet = |input| packet_parser(input).unwrap().1;
+        let compare = |left, right| packet(left).cmp(&packet(right));
+        assert_eq!(compare("[[1],[2,3,4]]", "[[1],4]"), Ordering::Less);
+        assert_eq!(compare("[9]", "[[8,7,6]]"), Ordering::Greater);
+        assert_eq!(compare("[[[]]]", "[[]]"), Ordering::Greater);
+        assert_eq!(compare("[]", "[[]]"), Ordering::Less);
+        assert_eq!(compare("[[2]]", "[2]"), Ordering::Equal);
+        assert_eq!(packet("[[2]]"), packet("[2]"));
+        assert!(packet("[[1],[2,3,4]]") < packet("[[1],4]"));
+        let set = BTreeSet::from([packet("[[2]]"), packet("[2]"), packet("[3]")]);
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn display_and_json() {
+        let input = "[1,[2,[3,[4,[5,6,123456789012345678901234567890]]]],[],8,9]";
+        let packet = input.parse::<Packet>().unwrap();
+        assert_eq!(packet.to_string(), input);
+
+        let value = Value::from(&packet);
+        assert_eq!(value.to_string(), input);
+        assert_eq!(
+            Packet::try_from(&value).map(|packet| packet.to_string()),
+            Ok(input.to_string())
+        );
+
+        let value = serde_json::json!([1, "2"]);
+        assert_eq!(
+            Packet::try_from(&value),
+            Err(PacketError::InvalidValue(serde_json::json!("2")))
+        );
+        assert!(Packet::try_from(&serde_json::json!([-1])).is_err());
+        assert!("[1,2".parse::<Packet>().is_err());
+    }
+
+    fn arb_packet() -> impl Strategy<Value = Packet> {
+        let leaf = any::<u128>().prop_map(|number| Packet::Number(BigUint::from(number)));
+        leaf.prop_recursive(4, 64, 8, |inner| {
+            prop::collection::vec(inner, 0..8).prop_map(Packet::List)
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn display_round_trip(packet in arb_packet()) {
+            let text = packet.to_string();
+            prop_assert_eq!(text.parse::<Packet>().map(|packet| packet.to_string()), Ok(text.clone()));
+            prop_assert_eq!(
+                Packet::try_from(&Value::from(&packet)).map(|packet| packet.to_string()),
+                Ok(text)
+            );
+        }
+    }
+
+    #[test]
+    fn explained_ordering() {
+        let explanations = explain_pairs(INPUT);
+        assert_eq!(
+            explanations[1].to_string(),
+            "- Compare [[1],[2,3,4]] vs [[1],4]
+  - Compare [1] vs [1]
+    - Compare 1 vs 1
+  - Compare [2,3,4] vs 4
+    - Mixed types; convert right to [4] and retry comparison
+    - Compare [2,3,4] vs [4]
+      - Compare 2 vs 4
+        - Left side is smaller, so inputs are in the right order
+"
+        );
+        assert_eq!(explanations[1].path, vec![1, 0]);
+        assert_eq!(explanations[3].decision, Some(Decision::RanOut(Side::Left)));
+        assert_eq!(explanations[3].path, vec![3]);
+        assert_eq!(explanations[7].path, vec![1, 1, 1, 1, 2]);
+        assert_eq!(
+            explanations[7].decision,
+            Some(Decision::Smaller(Side::Right))
+        );
+
+        let pairs = input_parser(INPUT);
+        for (pair, explanation) in pairs.iter().zip(&explanations) {
+            assert_eq!(explanation.ordering, pair.left.cmp(&pair.right));
+        }
+    }
+
+    #[test]
+    fn custom_dividers() {
+        let packets = parse_packets(INPUT).unwrap();
+        assert_eq!(packets.len(), 16);
+
+        let dividers = ["[[2]]", "[6]", "[]"].map(|divider| divider.parse::<Packet>().unwrap());
+        let sorted = sort_with_dividers(packets, &dividers);
+        assert_eq!(sorted.packets.len(), 19);
+        assert!(sorted.packets.windows(2).all(|pair| pair[0] <= pair[1]));
+        for (divider, idx) in dividers.iter().zip(&sorted.divider_indices) {
+            assert_eq!(sorted.packets[idx - 1].to_string(), divider.to_string());
+        }
+        assert_eq!(sorted.divider_indices, vec![11, 15, 2]);
     }
 }
@@ -0,0 +1,8 @@
+use std::fs::read_to_string;
+
+use day_14::part_2_fast;
+
+fn main() {
+    let input = read_to_string("input.txt").unwrap();
+    println!("{}", part_2_fast(&input));
+}
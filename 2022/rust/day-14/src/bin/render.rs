@@ -0,0 +1,35 @@
+use std::{env, fs::read_to_string};
+
+use day_14::{render, render_frames, Point, SandConfig};
+
+fn main() {
+    let mut config = SandConfig::default();
+    let mut frames = false;
+    let mut sources = vec![];
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--floor-offset" => config.floor_offset = Some(args.next().unwrap().parse().unwrap()),
+            "--frames" => frames = true,
+            "--source" => {
+                let source = args.next().unwrap();
+                let (x, y) = source.split_once(',').unwrap();
+                sources.push(Point {
+                    x: x.parse().unwrap(),
+                    y: y.parse().unwrap(),
+                });
+            }
+            _ => panic!("unknown argument {arg}"),
+        }
+    }
+    if !sources.is_empty() {
+        config.sources = sources;
+    }
+
+    let input = read_to_string("input.txt").unwrap();
+    if frames {
+        render_frames(&input, &config, |frame| println!("{frame}"));
+    } else {
+        print!("{}", render(&input, &config));
+    }
+}
@@ -1,31 +1,43 @@
-use std::collections::HashMap;
+use std::collections::HashSet;
 
 use nom::{
     bytes::complete::tag,
     character::complete::{self, newline},
     multi::separated_list1,
     sequence::separated_pair,
-    IResult, Parser,
+    IResult,
 };
 
-#[derive(Debug, PartialEq, Eq, Hash)]
-struct Point {
-    x: u32,
-    y: u32,
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 enum Unit {
     Sand,
     Rock,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SandConfig {
+    pub sources: Vec<Point>,
+    /// Places a floor this many rows below the lowest rock.
+    pub floor_offset: Option<i32>,
+}
+
+impl Default for SandConfig {
+    fn default() -> Self {
+        SandConfig {
+            sources: vec![Point { x: 500, y: 0 }],
+            floor_offset: None,
+        }
+    }
+}
+
 fn point_parser(input: &str) -> IResult<&str, Point> {
-    let (input, (x, y)) = separated_pair(
-        complete::digit1.map(|result: &str| result.parse::<u32>().unwrap()),
-        tag(","),
-        complete::digit1.map(|result: &str| result.parse::<u32>().unwrap()),
-    )(input)?;
+    let (input, (x, y)) = separated_pair(complete::i32, tag(","), complete::i32)(input)?;
     Ok((input, Point { x, y }))
 }
 
@@ -37,140 +49,261 @@ fn input_parser(input: &str) -> Vec<Vec<Point>> {
     separated_list1(newline, line_parser)(input).unwrap().1
 }
 
-fn area_map(input: Vec<Vec<Point>>) -> (HashMap<Point, Unit>, u32) {
-    let mut unit_map: HashMap<Point, Unit> = HashMap::new();
-    let mut max_y = 0;
+struct Cave {
+    cells: Vec<Option<Unit>>,
+    sources: Vec<Point>,
+    min_x: i32,
+    min_y: i32,
+    width: i32,
+    max_y: i32,
+    floor_y: Option<i32>,
+}
 
-    for line in input {
-        for i in 0..(line.len() - 1) {
-            let (first, second) = (line.get(i).unwrap(), line.get(i + 1).unwrap());
+impl Cave {
+    fn new(input: Vec<Vec<Point>>, config: &SandConfig) -> Self {
+        let max_y = input
+            .iter()
+            .flatten()
+            .map(|point| point.y)
+            .max()
+            .unwrap_or(0);
+        let floor_y = config.floor_offset.map(|offset| max_y + offset);
+        let bottom_y = config
+            .sources
+            .iter()
+            .map(|source| source.y + 1)
+            .chain(floor_y)
+            .fold(max_y + 1, i32::max);
+        let rocks = input.iter().flatten();
+        let reach = |source: &Point| (bottom_y - source.y).max(0) + 1;
+        let min_x = rocks
+            .clone()
+            .map(|point| point.x)
+            .chain(config.sources.iter().map(|source| source.x - reach(source)))
+            .min()
+            .unwrap();
+        let max_x = rocks
+            .clone()
+            .map(|point| point.x)
+            .chain(config.sources.iter().map(|source| source.x + reach(source)))
+            .max()
+            .unwrap();
+        let min_y = rocks
+            .map(|point| point.y)
+            .chain(config.sources.iter().map(|source| source.y))
+            .min()
+            .unwrap();
+        let width = max_x - min_x + 1;
+        let mut cave = Cave {
+            cells: vec![None; (width * (bottom_y - min_y + 1)) as usize],
+            sources: config.sources.clone(),
+            min_x,
+            min_y,
+            width,
+            max_y,
+            floor_y,
+        };
 
-            let x_range = if first.x < second.x {
-                first.x..=second.x
-            } else {
-                second.x..=first.x
-            };
-            for x in x_range {
-                let y_range = if first.y < second.y {
-                    first.y..=second.y
-                } else {
-                    second.y..=first.y
-                };
-
-                for y in y_range {
-                    if y > max_y {
-                        max_y = y;
+        for line in input {
+            for pair in line.windows(2) {
+                let (first, second) = (&pair[0], &pair[1]);
+                for x in first.x.min(second.x)..=first.x.max(second.x) {
+                    for y in first.y.min(second.y)..=first.y.max(second.y) {
+                        cave.set(&Point { x, y }, Unit::Rock);
                     }
-                    unit_map.insert(Point { x, y }, Unit::Rock);
                 }
             }
         }
+        cave
     }
-    (unit_map, max_y)
-}
 
-fn next_point(
-    current_point: &Point,
-    unit_map: &HashMap<Point, Unit>,
-    floor_y: Option<u32>,
-) -> Option<Point> {
-    let y = current_point.y + 1;
-    let down = Point {
-        x: current_point.x,
-        y,
-    };
-    let left_down = Point {
-        x: current_point.x - 1,
-        y,
-    };
-    let right_down = Point {
-        x: current_point.x + 1,
-        y,
-    };
+    fn index(&self, point: &Point) -> usize {
+        ((point.y - self.min_y) * self.width + point.x - self.min_x) as usize
+    }
+
+    fn get(&self, point: &Point) -> Option<Unit> {
+        self.cells[self.index(point)]
+    }
 
-    if floor_y.is_some() && current_point.y == floor_y.unwrap() - 1 {
-        return None;
+    fn set(&mut self, point: &Point, unit: Unit) {
+        let index = self.index(point);
+        self.cells[index] = Some(unit);
     }
 
-    if unit_map.get(&down).is_none() {
-        return Some(down);
+    fn next_point(&self, current_point: &Point) -> Option<Point> {
+        if self.floor_y == Some(current_point.y + 1) {
+            return None;
+        }
+
+        let y = current_point.y + 1;
+        [
+            Point {
+                x: current_point.x,
+                y,
+            },
+            Point {
+                x: current_point.x - 1,
+                y,
+            },
+            Point {
+                x: current_point.x + 1,
+                y,
+            },
+        ]
+        .into_iter()
+        .find(|point| self.get(point).is_none())
     }
 
-    if unit_map.get(&left_down).is_none() {
-        return Some(left_down);
+    fn fill(&mut self) -> Vec<usize> {
+        self.fill_with(|_, _| {})
     }
 
-    if unit_map.get(&right_down).is_none() {
-        return Some(right_down);
+    fn fill_with(&mut self, mut on_frame: impl FnMut(&Cave, &[Point])) -> Vec<usize> {
+        let sources = self.sources.clone();
+        sources
+            .iter()
+            .map(|source| self.fill_source(*source, &mut on_frame))
+            .collect()
     }
-    None
-}
 
-pub fn part_1(input: &str) -> usize {
-    let parsed = input_parser(input);
-    let (mut unit_map, max_y) = area_map(parsed);
-    let mut all_rested = false;
-
-    while !all_rested {
-        let mut rested = false;
-        let mut current_point = Point { x: 500, y: 0 };
-
-        while !rested {
-            if current_point.y > max_y {
-                rested = true;
-                all_rested = true;
-            }
+    fn fill_source(&mut self, source: Point, on_frame: &mut impl FnMut(&Cave, &[Point])) -> usize {
+        if self.get(&source).is_some() || self.floor_y.is_some_and(|floor_y| source.y >= floor_y) {
+            return 0;
+        }
+        let mut path = vec![source];
+        let mut rested = 0;
 
-            if let Some(next_point) = next_point(&current_point, &unit_map, None) {
-                current_point = next_point;
-            } else {
-                rested = true;
+        while let Some(current_point) = path.last() {
+            match self.next_point(current_point) {
+                Some(next_point) if self.floor_y.is_none() && next_point.y > self.max_y => {
+                    path.push(next_point);
+                    on_frame(self, &path);
+                    break;
+                }
+                Some(next_point) => path.push(next_point),
+                None => {
+                    let current_point = path.pop().unwrap();
+                    self.set(&current_point, Unit::Sand);
+                    rested += 1;
+                    on_frame(self, &path);
+                }
             }
         }
+        rested
+    }
 
-        unit_map.insert(current_point, Unit::Sand);
+    fn reachable(&self, source: Point, floor_y: i32) -> usize {
+        if self.get(&source).is_some() || source.y >= floor_y {
+            return 0;
+        }
+        let mut row = vec![false; self.width as usize];
+        row[(source.x - self.min_x) as usize] = true;
+        let mut count = 1;
+
+        for y in source.y + 1..floor_y {
+            let next_row = (0..row.len())
+                .map(|idx| {
+                    let point = Point {
+                        x: idx as i32 + self.min_x,
+                        y,
+                    };
+                    self.get(&point).is_none()
+                        && row[idx.saturating_sub(1)..=(idx + 1).min(row.len() - 1)]
+                            .iter()
+                            .any(|reached| *reached)
+                })
+                .collect::<Vec<_>>();
+            count += next_row.iter().filter(|reached| **reached).count();
+            row = next_row;
+        }
+        count
     }
 
-    unit_map
-        .iter()
-        .filter(|(_, unit)| match unit {
-            Unit::Sand => true,
-            _ => false,
-        })
-        .count()
-        - 1
-}
+    fn point(&self, index: usize) -> Point {
+        let index = index as i32;
+        Point {
+            x: index % self.width + self.min_x,
+            y: index / self.width + self.min_y,
+        }
+    }
 
-pub fn part_2(input: &str) -> usize {
-    let parsed = input_parser(input);
-    let (mut unit_map, max_y) = area_map(parsed);
-    let mut all_rested = false;
-
-    while !all_rested {
-        let mut rested = false;
-        let mut current_point = Point { x: 500, y: 0 };
-
-        while !rested {
-            if let Some(next_point) = next_point(&current_point, &unit_map, Some(max_y + 2)) {
-                current_point = next_point;
-            } else {
-                if current_point.y == 0 {
-                    all_rested = true;
-                }
-                rested = true;
+    fn render(&self, falling: &[Point]) -> String {
+        let falling = falling.iter().collect::<HashSet<_>>();
+        let points = self
+            .cells
+            .iter()
+            .enumerate()
+            .filter(|(_, unit)| unit.is_some())
+            .map(|(index, _)| self.point(index))
+            .chain(self.sources.iter().copied())
+            .chain(falling.iter().map(|point| **point))
+            .collect::<Vec<_>>();
+        let min_x = points.iter().map(|point| point.x).min().unwrap();
+        let max_x = points.iter().map(|point| point.x).max().unwrap();
+        let min_y = points.iter().map(|point| point.y).min().unwrap();
+        let max_y = self
+            .floor_y
+            .unwrap_or_else(|| points.iter().map(|point| point.y).max().unwrap());
+
+        let mut output = String::new();
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                let point = Point { x, y };
+                let char = if self.floor_y == Some(y) {
+                    '#'
+                } else {
+                    match self.get(&point) {
+                        Some(Unit::Rock) => '#',
+                        Some(Unit::Sand) => 'o',
+                        None if self.sources.contains(&point) => '+',
+                        None if falling.contains(&point) => '~',
+                        None => '.',
+                    }
+                };
+                output.push(char);
             }
+            output.push('\n');
         }
-
-        unit_map.insert(current_point, Unit::Sand);
+        output
     }
+}
+
+pub fn simulate(input: &str, config: &SandConfig) -> Vec<usize> {
+    Cave::new(input_parser(input), config).fill()
+}
 
-    unit_map
-        .iter()
-        .filter(|(_, unit)| match unit {
-            Unit::Sand => true,
-            _ => false,
-        })
-        .count()
+pub fn part_1(input: &str) -> usize {
+    simulate(input, &SandConfig::default()).iter().sum()
+}
+
+pub fn part_2(input: &str) -> usize {
+    let config = SandConfig {
+        floor_offset: Some(2),
+        ..SandConfig::default()
+    };
+    simulate(input, &config).iter().sum()
+}
+
+pub fn part_2_fast(input: &str) -> usize {
+    let config = SandConfig {
+        floor_offset: Some(2),
+        ..SandConfig::default()
+    };
+    let cave = Cave::new(input_parser(input), &config);
+    cave.reachable(config.sources[0], cave.floor_y.unwrap())
+}
+
+pub fn render(input: &str, config: &SandConfig) -> String {
+    let mut cave = Cave::new(input_parser(input), config);
+    let mut falling = vec![];
+    cave.fill_with(|_, path| falling = path.to_vec());
+    cave.render(&falling)
+}
+
+pub fn render_frames(input: &str, config: &SandConfig, mut on_frame: impl FnMut(String)) {
+    let mut cave = Cave::new(input_parser(input), config);
+    on_frame(cave.render(&[]));
+    cave.fill_with(|cave, path| on_frame(cave.render(path)));
 }
 
 #[cfg(test)]
@@ -189,4 +322,92 @@ mod tests {
     fn blocked_sand_count() {
         assert_eq!(part_2(INPUT), 93);
     }
+
+    #[test]
+    fn blocked_sand_count_fast() {
+        assert_eq!(part_2_fast(INPUT), 93);
+        for input in [
+            "500,2 -> 500,2",
+            "490,5 -> 510,5\n499,2 -> 501,2",
+            "495,3 -> 495,8 -> 505,8 -> 505,3\n500,1 -> 500,4",
+            "500,0 -> 500,0\n490,5 -> 510,5",
+        ] {
+            assert_eq!(part_2_fast(input), part_2(input));
+        }
+    }
+
+    #[test]
+    fn rendered_cave() {
+        assert_eq!(
+            render(INPUT, &SandConfig::default()),
+            ".......+...
+.......~...
+......~o...
+.....~ooo..
+....~#ooo##
+...~o#ooo#.
+..~###ooo#.
+..~..oooo#.
+.~o.ooooo#.
+~#########.
+~..........
+"
+        );
+
+        let mut frames = vec![];
+        render_frames(INPUT, &SandConfig::default(), |frame| frames.push(frame));
+        assert_eq!(frames.len(), 26);
+        assert_eq!(
+            frames[2],
+            "......+...
+......~...
+......~...
+......~...
+....#.~.##
+....#.~.#.
+..###.~.#.
+......~.#.
+.....oo.#.
+#########.
+"
+        );
+    }
+
+    #[test]
+    fn multiple_sources() {
+        let config = SandConfig {
+            sources: vec![Point { x: 500, y: 0 }, Point { x: 1, y: 0 }],
+            floor_offset: None,
+        };
+        assert_eq!(simulate(INPUT, &config), vec![24, 0]);
+
+        let config = SandConfig {
+            sources: vec![Point { x: 500, y: 20 }],
+            floor_offset: None,
+        };
+        assert_eq!(simulate("498,4 -> 498,6", &config), vec![0]);
+        let config = SandConfig {
+            floor_offset: Some(2),
+            ..config
+        };
+        assert_eq!(simulate("498,4 -> 498,6", &config), vec![0]);
+
+        let config = SandConfig {
+            sources: vec![Point { x: 1, y: 0 }, Point { x: 1, y: -2 }],
+            floor_offset: Some(2),
+        };
+        let counts = simulate("0,2 -> 2,2", &config);
+        assert_eq!(counts, vec![12, 20]);
+        assert_eq!(
+            render("0,2 -> 2,2", &config),
+            ".....o.....
+....ooo....
+...ooooo...
+..ooooooo..
+.ooo###ooo.
+ooooo.ooooo
+###########
+"
+        );
+    }
 }
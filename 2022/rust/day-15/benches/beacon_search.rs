@@ -0,0 +1,22 @@
+use std::fs::read_to_string;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use day_15::{find_beacon, parse_sensors};
+
+fn beacon_search(c: &mut Criterion) {
+    let input = read_to_string("input.txt").unwrap();
+    let sensors = parse_sensors(&input).unwrap();
+    let mut group = c.benchmark_group("beacon_search");
+    group.sample_size(10);
+    group.bench_function("sequential", |b| {
+        b.iter(|| find_beacon(&sensors, 4_000_000))
+    });
+    #[cfg(feature = "parallel")]
+    group.bench_function("parallel", |b| {
+        b.iter(|| day_15::find_beacon_parallel(&sensors, 4_000_000))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, beacon_search);
+criterion_main!(benches);
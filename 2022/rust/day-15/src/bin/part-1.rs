@@ -1,8 +1,14 @@
-use std::fs::read_to_string;
+use std::{fs::read_to_string, process::exit};
 
 use day_15::part_1;
 
 fn main() {
     let input = read_to_string("input.txt").unwrap();
-    println!("{}", part_1(&input, 2000000))
+    match part_1(&input, 2_000_000) {
+        Ok(count) => println!("{count}"),
+        Err(err) => {
+            eprintln!("{err}");
+            exit(1);
+        }
+    }
 }
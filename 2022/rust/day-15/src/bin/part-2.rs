@@ -0,0 +1,14 @@
+use std::{fs::read_to_string, process::exit};
+
+use day_15::part_2;
+
+fn main() {
+    let input = read_to_string("input.txt").unwrap();
+    match part_2(&input, 4_000_000) {
+        Ok(frequency) => println!("{frequency:?}"),
+        Err(err) => {
+            eprintln!("{err}");
+            exit(1);
+        }
+    }
+}
@@ -1,26 +1,88 @@
+use std::{error::Error as StdError, fmt, ops::RangeInclusive};
+
 use nom::{
     branch::alt,
     bytes::complete::tag,
-    character::complete::{self, newline},
-    multi::{many1, separated_list1},
+    character::complete,
+    combinator::all_consuming,
+    error::{Error, ErrorKind},
+    multi::many1,
     sequence::{preceded, separated_pair},
     IResult,
 };
-#[derive(Debug)]
-struct Coord {
-    x: i32,
-    y: i32,
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Coord {
+    pub x: i64,
+    pub y: i64,
+}
+
+impl Coord {
+    pub fn distance(&self, other: &Coord) -> Option<i64> {
+        let x = self.x.checked_sub(other.x)?.checked_abs()?;
+        let y = self.y.checked_sub(other.y)?.checked_abs()?;
+        x.checked_add(y)
+    }
+
+    pub fn tuning_frequency(&self) -> Option<i64> {
+        self.x.checked_mul(4_000_000)?.checked_add(self.y)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SensorError {
+    InvalidSyntax(usize),
+    Overflow(usize),
+}
+
+impl fmt::Display for SensorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SensorError::InvalidSyntax(line) => write!(f, "line {line}: invalid sensor report"),
+            SensorError::Overflow(line) => {
+                write!(f, "line {line}: sensor radius does not fit in an i64")
+            }
+        }
+    }
 }
-#[derive(Debug)]
-struct Sensor {
-    coord: Coord,
-    beacon: Coord,
+
+impl StdError for SensorError {}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Sensor {
+    pub coord: Coord,
+    pub beacon: Coord,
+    radius: i64,
+}
+
+impl Sensor {
+    pub fn radius(&self) -> i64 {
+        self.radius
+    }
+
+    pub fn covers(&self, point: &Coord) -> bool {
+        self.coord
+            .distance(point)
+            .is_some_and(|distance| distance <= self.radius)
+    }
+
+    fn row_range(&self, y: i64) -> Option<(i64, i64)> {
+        let spread = self
+            .radius
+            .checked_sub(self.coord.y.checked_sub(y)?.checked_abs()?)?;
+        (spread >= 0).then(|| {
+            (
+                self.coord.x.saturating_sub(spread),
+                self.coord.x.saturating_add(spread),
+            )
+        })
+    }
 }
+
 fn coord_parser(input: &str) -> IResult<&str, Coord> {
     let (input, (x, y)) = separated_pair(
-        preceded(tag("="), complete::i32),
+        preceded(tag("="), complete::i64),
         tag(", "),
-        preceded(tag("y="), complete::i32),
+        preceded(tag("y="), complete::i64),
     )(input)?;
     Ok((input, Coord { x, y }))
 }
@@ -35,40 +97,247 @@ fn sensor_parser(input: &str) -> IResult<&str, Sensor> {
         many1(alt((complete::alpha1, complete::space1))),
         coord_parser,
     )(input)?;
+    let radius = coord
+        .distance(&beacon)
+        .ok_or_else(|| nom::Err::Failure(Error::new(input, ErrorKind::TooLarge)))?;
+
+    Ok((
+        input,
+        Sensor {
+            coord,
+            beacon,
+            radius,
+        },
+    ))
+}
+
+pub fn parse_sensors(input: &str) -> Result<Vec<Sensor>, SensorError> {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(
+            |(idx, line)| match all_consuming(sensor_parser)(line.trim()) {
+                Ok((_, sensor)) => Ok(sensor),
+                Err(nom::Err::Failure(error)) if error.code == ErrorKind::TooLarge => {
+                    Err(SensorError::Overflow(idx + 1))
+                }
+                Err(_) => Err(SensorError::InvalidSyntax(idx + 1)),
+            },
+        )
+        .collect()
+}
+
+fn merged_row_ranges(sensors: &[Sensor], y: i64) -> Vec<(i64, i64)> {
+    let mut ranges = sensors
+        .iter()
+        .filter_map(|sensor| sensor.row_range(y))
+        .collect::<Vec<_>>();
+    ranges.sort();
+
+    let mut merged: Vec<(i64, i64)> = vec![];
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1.saturating_add(1) => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+pub fn part_1(input: &str, target: i64) -> Result<usize, SensorError> {
+    let sensors = parse_sensors(input)?;
+    let ranges = merged_row_ranges(&sensors, target);
+    let covered = ranges
+        .iter()
+        .map(|(start, end)| (*end as i128 - *start as i128 + 1) as usize)
+        .sum::<usize>();
+
+    let mut beacons = sensors
+        .iter()
+        .map(|sensor| sensor.beacon)
+        .filter(|beacon| beacon.y == target)
+        .map(|beacon| beacon.x)
+        .collect::<Vec<_>>();
+    beacons.sort();
+    beacons.dedup();
+    let blocked = beacons
+        .iter()
+        .filter(|x| ranges.iter().any(|(start, end)| (start..=end).contains(x)))
+        .count();
+
+    Ok(covered - blocked)
+}
+
+fn uncovered_in_row(sensors: &[Sensor], y: i64, max: i64) -> Option<Coord> {
+    let mut ranges = sensors
+        .iter()
+        .filter_map(|sensor| sensor.row_range(y))
+        .collect::<Vec<_>>();
+    ranges.sort();
+
+    let mut x = 0;
+    for (start, end) in ranges {
+        if start > x || x > max {
+            break;
+        }
+        x = x.max(end.saturating_add(1));
+    }
+    (x <= max).then_some(Coord { x, y })
+}
 
-    Ok((input, Sensor { coord, beacon }))
+pub fn find_beacon(sensors: &[Sensor], max: i64) -> Option<Coord> {
+    (0..=max).find_map(|y| uncovered_in_row(sensors, y, max))
 }
 
-fn input_parser(input: &str) -> Vec<Sensor> {
-    separated_list1(newline, sensor_parser)(input).unwrap().1
+#[cfg(feature = "parallel")]
+pub fn find_beacon_parallel(sensors: &[Sensor], max: i64) -> Option<Coord> {
+    use rayon::prelude::*;
+
+    (0..=max)
+        .into_par_iter()
+        .find_map_first(|y| uncovered_in_row(sensors, y, max))
 }
 
-pub fn part_1(input: &str, target: i32) -> usize {
-    let sensors = input_parser(input);
-    let mut ranges = vec![];
+pub fn part_2(input: &str, max: i64) -> Result<Option<i64>, SensorError> {
+    let sensors = parse_sensors(input)?;
+    #[cfg(feature = "parallel")]
+    let beacon = find_beacon_parallel(&sensors, max);
+    #[cfg(not(feature = "parallel"))]
+    let beacon = find_beacon(&sensors, max);
+    Ok(beacon.and_then(|beacon| beacon.tuning_frequency()))
+}
 
-    sensors
+fn count_with_parity(start: i128, end: i128, parity: i128) -> u64 {
+    let first = start + (parity - start).rem_euclid(2);
+    if first > end {
+        0
+    } else {
+        ((end - first) / 2 + 1) as u64
+    }
+}
+
+pub fn covered_area(
+    input: &str,
+    x_range: RangeInclusive<i64>,
+    y_range: RangeInclusive<i64>,
+) -> Result<u64, SensorError> {
+    let sensors = parse_sensors(input)?;
+    let (min_x, max_x) = (*x_range.start() as i128, *x_range.end() as i128);
+    let (min_y, max_y) = (*y_range.start() as i128, *y_range.end() as i128);
+
+    // Each diamond is a square in (u, v) = (x + y, x - y) space, where only
+    // cells with matching u and v parity map back to integer (x, y) cells.
+    let squares = sensors
         .iter()
-        .filter(|Sensor { coord, beacon }| {
-            let distance = i32::abs(coord.x - beacon.x) + i32::abs(coord.y - beacon.y);
-            let distance_to_target = i32::abs(coord.y - target);
-            distance_to_target <= distance
+        .map(|sensor| {
+            let (x, y, distance) = (
+                sensor.coord.x as i128,
+                sensor.coord.y as i128,
+                sensor.radius as i128,
+            );
+            (
+                x + y - distance..=x + y + distance,
+                x - y - distance..=x - y + distance,
+            )
         })
-        .for_each(|Sensor { coord, beacon }| {
-            let distance = i32::abs(coord.x - beacon.x) + i32::abs(coord.y - beacon.y);
-            let distance_to_target = i32::abs(coord.y - target);
+        .collect::<Vec<_>>();
 
-            let x_range =
-                coord.x - distance + distance_to_target..coord.x + distance - distance_to_target;
+    let mut boundaries = squares
+        .iter()
+        .flat_map(|(u_range, _)| [*u_range.start(), u_range.end() + 1])
+        .chain([min_x + min_y, max_x + max_y + 1])
+        .filter(|u| (min_x + min_y..=max_x + max_y + 1).contains(u))
+        .collect::<Vec<_>>();
+    boundaries.sort();
+    boundaries.dedup();
 
-            ranges.push(x_range);
-        });
+    let mut area = 0;
+    for slab in boundaries.windows(2) {
+        let mut v_ranges = squares
+            .iter()
+            .filter(|(u_range, _)| u_range.contains(&slab[0]))
+            .map(|(_, v_range)| (*v_range.start(), *v_range.end()))
+            .collect::<Vec<_>>();
+        v_ranges.sort();
+        let merged =
+            v_ranges
+                .into_iter()
+                .fold(vec![], |mut merged: Vec<(i128, i128)>, (start, end)| {
+                    match merged.last_mut() {
+                        Some(last) if start <= last.1 + 1 => last.1 = last.1.max(end),
+                        _ => merged.push((start, end)),
+                    }
+                    merged
+                });
+        if merged.is_empty() {
+            continue;
+        }
 
-    ranges
-        .into_iter()
-        .reduce(|accum, current| accum.start.min(current.start)..accum.end.max(current.end))
-        .unwrap()
-        .count()
+        for u in slab[0]..slab[1] {
+            let v_min = (u - 2 * max_y).max(2 * min_x - u);
+            let v_max = (u - 2 * min_y).min(2 * max_x - u);
+            area += merged
+                .iter()
+                .map(|(start, end)| count_with_parity(*start.max(&v_min), *end.min(&v_max), u))
+                .sum::<u64>();
+        }
+    }
+    Ok(area)
+}
+
+pub fn render(
+    input: &str,
+    x_range: RangeInclusive<i64>,
+    y_range: RangeInclusive<i64>,
+) -> Result<String, SensorError> {
+    let sensors = parse_sensors(input)?;
+    let label_width = y_range
+        .clone()
+        .map(|y| y.to_string().len())
+        .max()
+        .unwrap_or(0);
+    let header_height = x_range
+        .clone()
+        .filter(|x| x % 5 == 0)
+        .map(|x| x.to_string().len())
+        .max()
+        .unwrap_or(0);
+
+    let mut output = String::new();
+    for line in 0..header_height {
+        let header = x_range
+            .clone()
+            .map(|x| {
+                let label = x.to_string();
+                let offset = header_height - label.len();
+                match x % 5 == 0 && line >= offset {
+                    true => label.chars().nth(line - offset).unwrap(),
+                    false => ' ',
+                }
+            })
+            .collect::<String>();
+        output.push_str(&format!("{:label_width$} {}\n", "", header.trim_end()));
+    }
+
+    for y in y_range {
+        output.push_str(&format!("{y:>label_width$} "));
+        for x in x_range.clone() {
+            let point = Coord { x, y };
+            let char = if sensors.iter().any(|sensor| sensor.coord == point) {
+                'S'
+            } else if sensors.iter().any(|sensor| sensor.beacon == point) {
+                'B'
+            } else if sensors.iter().any(|sensor| sensor.covers(&point)) {
+                '#'
+            } else {
+                '.'
+            };
+            output.push(char);
+        }
+        output.push('\n');
+    }
+    Ok(output)
 }
 
 #[cfg(test)]
@@ -91,6 +360,106 @@ Sensor at x=20, y=1: closest beacon is at x=15, y=3";
 
     #[test]
     fn it_works() {
-        assert_eq!(part_1(INPUT, 10), 26);
+        assert_eq!(part_1(INPUT, 10), Ok(26));
+    }
+
+    #[test]
+    fn row_coverage() {
+        let input = "Sensor at x=0, y=0: closest beacon is at x=1, y=0";
+        assert_eq!(part_1(input, 1000), Ok(0));
+
+        let input = "Sensor at x=0, y=0: closest beacon is at x=1, y=0
+Sensor at x=10, y=0: closest beacon is at x=11, y=0";
+        assert_eq!(part_1(input, 0), Ok(4));
+
+        let input = "Sensor at x=0, y=0: closest beacon is at x=0, y=5";
+        assert_eq!(part_1(input, 0), Ok(11));
+        assert_eq!(part_1(input, 5), Ok(0));
+    }
+
+    #[test]
+    fn distress_beacon() {
+        assert_eq!(part_2(INPUT, 20), Ok(Some(56000011)));
+        assert_eq!(
+            find_beacon(&parse_sensors(INPUT).unwrap(), 20),
+            Some(Coord { x: 14, y: 11 })
+        );
+        assert_eq!(find_beacon(&parse_sensors(INPUT).unwrap(), 10), None);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn parallel_distress_beacon() {
+        let sensors = parse_sensors(INPUT).unwrap();
+        for max in [10, 20, 30] {
+            assert_eq!(
+                find_beacon_parallel(&sensors, max),
+                find_beacon(&sensors, max)
+            );
+        }
+    }
+
+    #[test]
+    fn total_covered_area() {
+        assert_eq!(covered_area(INPUT, 0..=20, 0..=20), Ok(440));
+
+        let sensors = parse_sensors(INPUT).unwrap();
+        let brute_force = (-10..=30)
+            .flat_map(|y| (-5..=35).map(move |x| Coord { x, y }))
+            .filter(|point| sensors.iter().any(|sensor| sensor.covers(point)))
+            .count() as u64;
+        assert_eq!(covered_area(INPUT, -5..=35, -10..=30), Ok(brute_force));
+    }
+
+    #[test]
+    fn rendered_coverage() {
+        assert_eq!(
+            render(INPUT, -4..=26, 9..=11).unwrap(),
+            "                 1    1    2    2
+       0    5    0    5    0    5
+ 9 ...#########################...
+10 ..####B######################..
+11 .###S#############.###########.
+"
+        );
+    }
+
+    #[test]
+    fn large_coordinates() {
+        let sensors = parse_sensors(INPUT).unwrap();
+        assert_eq!(sensors[6].radius(), 9);
+        assert!(sensors[6].covers(&Coord { x: 8, y: -2 }));
+        assert!(!sensors[6].covers(&Coord { x: 8, y: -3 }));
+        assert!(!sensors[6].covers(&Coord {
+            x: i64::MIN,
+            y: i64::MIN
+        }));
+        assert_eq!(Coord { x: 14, y: 11 }.tuning_frequency(), Some(56000011));
+        assert_eq!(Coord { x: i64::MAX, y: 0 }.tuning_frequency(), None);
+
+        let input = "Sensor at x=5000000000, y=-5000000000: closest beacon is at x=5000000003, y=-5000000000";
+        let sensors = parse_sensors(input).unwrap();
+        assert_eq!(sensors[0].radius(), 3);
+        assert_eq!(part_1(input, -5000000000), Ok(6));
+    }
+
+    #[test]
+    fn malformed_reports() {
+        let input = format!(
+            "{}\nSensor at x={}, y=0: closest beacon is at x={}, y=0",
+            INPUT.lines().next().unwrap(),
+            i64::MAX,
+            i64::MIN
+        );
+        assert_eq!(parse_sensors(&input).unwrap_err(), SensorError::Overflow(2));
+        assert_eq!(part_2(&input, 20), Err(SensorError::Overflow(2)));
+        assert_eq!(
+            part_1("Sensor at x=2, y=18: closest beacon", 10),
+            Err(SensorError::InvalidSyntax(1))
+        );
+        assert_eq!(
+            SensorError::Overflow(2).to_string(),
+            "line 2: sensor radius does not fit in an i64"
+        );
     }
 }
@@ -1,8 +1,14 @@
-use std::fs::read_to_string;
+use std::{fs::read_to_string, process::exit};
 
 use day_5::part_2;
 
 fn main() {
     let input = read_to_string("input.txt").unwrap();
-    println!("{}", part_2(input));
+    match part_2(input) {
+        Ok(tops) => println!("{tops}"),
+        Err(err) => {
+            eprintln!("{err}");
+            exit(1);
+        }
+    }
 }
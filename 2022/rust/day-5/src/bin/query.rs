@@ -0,0 +1,42 @@
+use std::{env, fs::read_to_string, process::exit};
+
+use day_5::{parse, CrateMover9000, History};
+
+fn main() {
+    let input = read_to_string("input.txt").unwrap();
+    let (stacks, instructions) = match parse(&input) {
+        Ok(parsed) => parsed,
+        Err(err) => {
+            eprintln!("{err}");
+            exit(1);
+        }
+    };
+    let history = match History::build(stacks, &instructions, &CrateMover9000) {
+        Ok(history) => history,
+        Err(err) => {
+            eprintln!("{err}");
+            exit(1);
+        }
+    };
+
+    let mut args = env::args().skip(1);
+    let step = args
+        .next()
+        .and_then(|step| step.parse().ok())
+        .unwrap_or(history.len());
+    let Some(stacks) = history.at(step) else {
+        eprintln!(
+            "step {step} is past the last of {} instructions",
+            history.len()
+        );
+        exit(1);
+    };
+    match args.next() {
+        Some(crate_name) => {
+            for position in stacks.locate(&crate_name) {
+                println!("stack {} level {}", position.stack, position.level);
+            }
+        }
+        None => println!("{stacks}"),
+    }
+}
@@ -0,0 +1,27 @@
+use std::{env, fs::read_to_string, process::exit};
+
+use day_5::{parse, CrateMover9000, CrateMover9001, Instruction, Stacks};
+
+fn main() {
+    let input = read_to_string("input.txt").unwrap();
+    let (stacks, instructions) = match parse(&input) {
+        Ok(parsed) => parsed,
+        Err(err) => {
+            eprintln!("{err}");
+            exit(1);
+        }
+    };
+    println!("{stacks}\n");
+
+    let print_step = |step: usize, instruction: &Instruction, stacks: &Stacks| {
+        println!("{step}: {instruction}\n{stacks}\n");
+    };
+    let result = match env::args().nth(1).as_deref() {
+        Some("9001") => stacks.apply_with(&instructions, &CrateMover9001, print_step),
+        _ => stacks.apply_with(&instructions, &CrateMover9000, print_step),
+    };
+    if let Err(err) = result {
+        eprintln!("{err}");
+        exit(1);
+    }
+}
@@ -1,170 +1,842 @@
-use std::collections::HashMap;
+use std::{error::Error, fmt, mem};
 
-use nom::{
-    branch,
-    bytes::complete::tag,
-    character::complete::{self, newline},
-    multi::{many1, separated_list1},
-    IResult,
-};
+use nom::{bytes::complete::tag, character::complete, combinator::all_consuming, IResult};
 
-fn crate_parser(input: &str) -> IResult<&str, char> {
-    let (input, _) = tag("[")(input)?;
-    let (input, crate_name) = complete::anychar(input)?;
-    let (input, _) = tag("]")(input)?;
-    Ok((input, crate_name))
+fn is_number_row(line: &str) -> bool {
+    !line.trim().is_empty()
+        && line
+            .chars()
+            .all(|char| char.is_ascii_digit() || char.is_whitespace())
 }
 
-fn empty_slot(input: &str) -> IResult<&str, char> {
-    let (input, _) = tag("   ")(input)?;
-    Ok((input, ' '))
+fn tokens(
+    line: &str,
+    is_start: impl Fn(char) -> bool,
+    is_end: impl Fn(char) -> bool,
+) -> Vec<(usize, usize)> {
+    let mut tokens = vec![];
+    let mut start = None;
+    for (idx, char) in line.char_indices() {
+        match start {
+            None if is_start(char) => start = Some(idx),
+            Some(token_start) if is_end(char) => {
+                tokens.push((token_start, idx + char.len_utf8()));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(token_start) = start {
+        tokens.push((token_start, line.len()));
+    }
+    tokens
 }
 
-fn crate_or_empty(input: &str) -> IResult<&str, char> {
-    let (input, result) = branch::alt((crate_parser, empty_slot))(input)?;
-    Ok((input, result))
+fn instruction_part<'a>(keyword: &str, input: &'a str) -> IResult<&'a str, u32> {
+    let (input, _) = tag(keyword)(input)?;
+    let (input, _) = complete::space1(input)?;
+    let (input, digit) = complete::u32(input)?;
+    let (input, _) = complete::space0(input)?;
+    Ok((input, digit))
 }
 
-fn crate_line_parser(input: &str) -> IResult<&str, Vec<char>> {
-    let (input, crates) = separated_list1(tag(" "), crate_or_empty)(input)?;
-    Ok((input, crates))
+fn instruction(input: &str) -> IResult<&str, Instruction> {
+    let (input, amount) = instruction_part("move", input)?;
+    let (input, from) = instruction_part("from", input)?;
+    let (input, to) = instruction_part("to", input)?;
+
+    Ok((
+        input,
+        Instruction {
+            amount: amount as usize,
+            from: from as usize,
+            to: to as usize,
+        },
+    ))
 }
-fn total_crates(input: &str) -> IResult<&str, Vec<Vec<char>>> {
-    let (input, crates) = separated_list1(newline, crate_line_parser)(input)?;
-    let (input, _) = newline(input)?;
-    Ok((input, crates))
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Instruction {
+    pub amount: usize,
+    pub from: usize,
+    pub to: usize,
 }
-fn crate_number_parser(input: &str) -> IResult<&str, u8> {
-    let (input, _) = complete::space0(input)?;
-    let (input, digit) = complete::u8(input)?;
-    let (input, _) = complete::space0(input)?;
-    Ok((input, digit))
+
+pub trait Crane {
+    fn arrange(&self, lifted: Vec<String>) -> Vec<String>;
 }
 
-fn crate_number_parsing(input: &str) -> IResult<&str, Vec<u8>> {
-    let (input, result) = many1(crate_number_parser)(input)?;
-    Ok((input, result))
+pub struct CrateMover9000;
+
+impl Crane for CrateMover9000 {
+    fn arrange(&self, lifted: Vec<String>) -> Vec<String> {
+        lifted.into_iter().rev().collect()
+    }
 }
 
-fn instruction_part(input: &str) -> IResult<&str, u32> {
-    let (input, _) = many1(complete::alpha1)(input)?;
-    let (input, _) = complete::space1(input)?;
-    let (input, digit) = complete::u32(input)?;
-    let (input, _) = complete::space0(input)?;
-    Ok((input, digit))
+pub struct CrateMover9001;
+
+impl Crane for CrateMover9001 {
+    fn arrange(&self, lifted: Vec<String>) -> Vec<String> {
+        lifted
+    }
+}
+
+pub struct LimitedLift {
+    pub max: usize,
 }
 
-fn instruction(input: &str) -> IResult<&str, (u32, u32, u32)> {
-    let (input, amount) = instruction_part(input)?;
-    let (input, from) = instruction_part(input)?;
-    let (input, to) = instruction_part(input)?;
+impl Crane for LimitedLift {
+    fn arrange(&self, lifted: Vec<String>) -> Vec<String> {
+        lifted.rchunks(self.max.max(1)).flatten().cloned().collect()
+    }
+}
 
-    Ok((input, (amount, from, to)))
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Problem {
+    UnknownStack(usize),
+    InsufficientCrates { stack: usize, available: usize },
+    SameStack(usize),
 }
 
-fn instruction_list(input: &str) -> IResult<&str, Vec<(u32, u32, u32)>> {
-    let (input, list) = separated_list1(newline, instruction)(input)?;
-    Ok((input, list))
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InstructionError {
+    pub step: usize,
+    pub instruction: Instruction,
+    pub problem: Problem,
 }
 
-fn input_parser(input: &str) -> IResult<&str, Vec<(u32, u32, u32)>> {
-    let (input, _) = newline(input)?;
-    let (input, _) = newline(input)?;
-    let (input, instructions) = instruction_list(input)?;
-    Ok((input, instructions))
+impl fmt::Display for InstructionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "instruction {} ({}): ", self.step, self.instruction)?;
+        match self.problem {
+            Problem::UnknownStack(stack) => write!(f, "stack {stack} does not exist"),
+            Problem::InsufficientCrates { stack, available } => write!(
+                f,
+                "stack {stack} has {available} crates, cannot move {}",
+                self.instruction.amount
+            ),
+            Problem::SameStack(stack) => write!(f, "cannot move from stack {stack} to itself"),
+        }
+    }
 }
 
-pub fn part_1(input: String) -> String {
-    let mut stack_hash_map: HashMap<usize, Vec<char>> = HashMap::new();
+impl Error for InstructionError {}
 
-    let (input, crates) = total_crates(&input).unwrap();
-    let (input, _) = crate_number_parsing(input).unwrap();
-    let (_, instructions) = input_parser(&input).unwrap();
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    MissingNumberRow,
+    UnterminatedCrate { line: usize },
+    OverlappingCrates { line: usize, stack: usize },
+    InvalidInstruction { line: usize, text: String },
+}
 
-    for row in crates.iter().rev() {
-        for (idx, crate_name) in row.iter().enumerate() {
-            if !crate_name.is_whitespace() {
-                stack_hash_map
-                    .entry(idx + 1)
-                    .or_insert(Vec::new())
-                    .push(*crate_name);
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::MissingNumberRow => write!(f, "drawing has no stack number row"),
+            ParseError::UnterminatedCrate { line } => {
+                write!(f, "line {line}: crate is missing its closing ']'")
             }
+            ParseError::OverlappingCrates { line, stack } => {
+                write!(
+                    f,
+                    "line {line}: more than one crate sits over stack {stack}"
+                )
+            }
+            ParseError::InvalidInstruction { line, text } => {
+                write!(f, "line {line}: invalid instruction {text:?}")
+            }
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PuzzleError {
+    Parse(ParseError),
+    Instruction(InstructionError),
+}
+
+impl fmt::Display for PuzzleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PuzzleError::Parse(err) => write!(f, "{err}"),
+            PuzzleError::Instruction(err) => write!(f, "{err}"),
         }
     }
+}
+
+impl Error for PuzzleError {}
 
-    for (amount, from, to) in instructions {
-        let from_stack = stack_hash_map.get_mut(&(from as usize)).unwrap();
-        let amount = from_stack.len() - (amount as usize);
-        let mut moved = from_stack.drain(amount..).rev().collect();
-        stack_hash_map
-            .entry(to as usize)
-            .and_modify(|stack| stack.append(&mut moved));
+impl From<ParseError> for PuzzleError {
+    fn from(err: ParseError) -> Self {
+        PuzzleError::Parse(err)
     }
+}
 
-    let mut answer = "".to_string();
-    for key in 1..=stack_hash_map.len() {
-        answer.push(*stack_hash_map.get(&key).unwrap().last().unwrap());
+impl From<InstructionError> for PuzzleError {
+    fn from(err: InstructionError) -> Self {
+        PuzzleError::Instruction(err)
     }
+}
 
-    answer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stacks {
+    stacks: Vec<Vec<String>>,
 }
 
-pub fn part_2(input: String) -> String {
-    let mut stack_hash_map: HashMap<usize, Vec<char>> = HashMap::new();
+impl Stacks {
+    fn from_drawing(lines: &[&str]) -> Result<Self, ParseError> {
+        let (number_row, crate_rows) = lines.split_last().unwrap();
+        let centers = tokens(
+            number_row,
+            |char| char.is_ascii_digit(),
+            |char| !char.is_ascii_digit(),
+        )
+        .into_iter()
+        .map(|(start, end)| start + end - 1)
+        .collect::<Vec<_>>();
+
+        let mut stacks = vec![vec![]; centers.len()];
+        for (idx, line) in crate_rows.iter().enumerate().rev() {
+            let mut filled = vec![false; centers.len()];
+            for (start, end) in tokens(line, |char| char == '[', |char| char == ']') {
+                let cell = &line[start..end];
+                if cell.len() < 2 || !cell.ends_with(']') || cell[1..].contains('[') {
+                    return Err(ParseError::UnterminatedCrate { line: idx + 1 });
+                }
+                let center = start + end - 1;
+                let stack = (0..centers.len())
+                    .min_by_key(|stack| centers[*stack].abs_diff(center))
+                    .unwrap();
+                if mem::replace(&mut filled[stack], true) {
+                    return Err(ParseError::OverlappingCrates {
+                        line: idx + 1,
+                        stack: stack + 1,
+                    });
+                }
+                stacks[stack].push(cell[1..cell.len() - 1].to_string());
+            }
+        }
+        Ok(Stacks { stacks })
+    }
 
-    let (input, crates) = total_crates(&input).unwrap();
-    let (input, _) = crate_number_parsing(input).unwrap();
-    let (_, instructions) = input_parser(&input).unwrap();
+    pub fn apply(
+        self,
+        instructions: &[Instruction],
+        crane: &impl Crane,
+    ) -> Result<Self, InstructionError> {
+        self.apply_with(instructions, crane, |_, _, _| {})
+    }
 
-    for row in crates.iter().rev() {
-        for (idx, crate_name) in row.iter().enumerate() {
-            if !crate_name.is_whitespace() {
-                stack_hash_map
-                    .entry(idx + 1)
-                    .or_insert(Vec::new())
-                    .push(*crate_name);
+    pub fn apply_with(
+        mut self,
+        instructions: &[Instruction],
+        crane: &impl Crane,
+        mut on_step: impl FnMut(usize, &Instruction, &Stacks),
+    ) -> Result<Self, InstructionError> {
+        for (idx, instruction) in instructions.iter().enumerate() {
+            self.step(instruction, crane)
+                .map_err(|problem| InstructionError {
+                    step: idx + 1,
+                    instruction: *instruction,
+                    problem,
+                })?;
+            on_step(idx + 1, instruction, &self);
+        }
+        Ok(self)
+    }
+
+    pub fn check(&self, instruction: &Instruction) -> Result<(), Problem> {
+        for stack in [instruction.from, instruction.to] {
+            if stack == 0 || stack > self.stacks.len() {
+                return Err(Problem::UnknownStack(stack));
             }
         }
+        if instruction.from == instruction.to {
+            return Err(Problem::SameStack(instruction.from));
+        }
+        let available = self.stacks[instruction.from - 1].len();
+        if available < instruction.amount {
+            return Err(Problem::InsufficientCrates {
+                stack: instruction.from,
+                available,
+            });
+        }
+        Ok(())
+    }
+
+    pub fn step(&mut self, instruction: &Instruction, crane: &impl Crane) -> Result<(), Problem> {
+        self.check(instruction)?;
+        let lifted = self.take(instruction.from, instruction.amount);
+        self.stacks[instruction.to - 1].extend(crane.arrange(lifted));
+        Ok(())
+    }
+
+    fn take(&mut self, stack: usize, amount: usize) -> Vec<String> {
+        let stack = &mut self.stacks[stack - 1];
+        stack.drain(stack.len() - amount..).collect()
+    }
+
+    pub fn top(&self, stack: usize) -> Option<&str> {
+        self.stacks
+            .get(stack.checked_sub(1)?)?
+            .last()
+            .map(String::as_str)
+    }
+
+    pub fn locate(&self, crate_name: &str) -> Vec<Position> {
+        self.stacks
+            .iter()
+            .enumerate()
+            .flat_map(|(idx, stack)| {
+                stack
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, name)| *name == crate_name)
+                    .map(move |(level, _)| Position {
+                        stack: idx + 1,
+                        level,
+                    })
+            })
+            .collect()
+    }
+
+    pub fn tops(&self) -> Vec<Option<String>> {
+        self.stacks
+            .iter()
+            .map(|stack| stack.last().cloned())
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub stack: usize,
+    pub level: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Operation {
+    instruction: Instruction,
+    lifted: Vec<String>,
+    placed: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct History {
+    current: Stacks,
+    operations: Vec<Operation>,
+    cursor: usize,
+    checkpoints: Vec<Stacks>,
+    interval: usize,
+}
+
+impl History {
+    pub fn new(stacks: Stacks) -> Self {
+        History::with_interval(stacks, 1024)
+    }
+
+    pub fn with_interval(stacks: Stacks, interval: usize) -> Self {
+        History {
+            checkpoints: vec![stacks.clone()],
+            current: stacks,
+            operations: vec![],
+            cursor: 0,
+            interval: interval.max(1),
+        }
+    }
+
+    pub fn build(
+        stacks: Stacks,
+        instructions: &[Instruction],
+        crane: &impl Crane,
+    ) -> Result<Self, InstructionError> {
+        let mut history = History::new(stacks);
+        for (idx, instruction) in instructions.iter().enumerate() {
+            history
+                .record(instruction, crane)
+                .map_err(|problem| InstructionError {
+                    step: idx + 1,
+                    instruction: *instruction,
+                    problem,
+                })?;
+        }
+        Ok(history)
+    }
+
+    pub fn record(&mut self, instruction: &Instruction, crane: &impl Crane) -> Result<(), Problem> {
+        self.current.check(instruction)?;
+        self.operations.truncate(self.cursor);
+        self.checkpoints.truncate(self.cursor / self.interval + 1);
+
+        let lifted = self.current.take(instruction.from, instruction.amount);
+        let placed = crane.arrange(lifted.clone());
+        self.current.stacks[instruction.to - 1].extend(placed.iter().cloned());
+        self.operations.push(Operation {
+            instruction: *instruction,
+            lifted,
+            placed,
+        });
+        self.cursor += 1;
+        if self.cursor.is_multiple_of(self.interval) {
+            self.checkpoints.push(self.current.clone());
+        }
+        Ok(())
+    }
+
+    pub fn undo(&mut self) -> Option<Instruction> {
+        let operation = &self.operations[self.cursor.checked_sub(1)?];
+        let instruction = operation.instruction;
+        self.current.take(instruction.to, instruction.amount);
+        self.current.stacks[instruction.from - 1].extend(operation.lifted.iter().cloned());
+        self.cursor -= 1;
+        Some(instruction)
+    }
+
+    pub fn redo(&mut self) -> Option<Instruction> {
+        let operation = self.operations.get(self.cursor)?;
+        Self::replay(&mut self.current, operation);
+        self.cursor += 1;
+        Some(operation.instruction)
+    }
+
+    fn replay(stacks: &mut Stacks, operation: &Operation) {
+        let instruction = operation.instruction;
+        stacks.take(instruction.from, instruction.amount);
+        stacks.stacks[instruction.to - 1].extend(operation.placed.iter().cloned());
+    }
+
+    pub fn current(&self) -> &Stacks {
+        &self.current
+    }
+
+    pub fn step(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn len(&self) -> usize {
+        self.operations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+
+    pub fn at(&self, step: usize) -> Option<Stacks> {
+        if step > self.operations.len() {
+            return None;
+        }
+        let checkpoint = (step / self.interval).min(self.checkpoints.len() - 1);
+        let mut stacks = self.checkpoints[checkpoint].clone();
+        for operation in &self.operations[checkpoint * self.interval..step] {
+            Self::replay(&mut stacks, operation);
+        }
+        Some(stacks)
+    }
+
+    pub fn top_at(&self, stack: usize, step: usize) -> Option<String> {
+        self.at(step)?.top(stack).map(str::to_string)
     }
 
-    for (amount, from, to) in instructions {
-        let from_stack = stack_hash_map.get_mut(&(from as usize)).unwrap();
-        let amount = from_stack.len() - (amount as usize);
-        let mut moved = from_stack.drain(amount..).collect();
-        stack_hash_map
-            .entry(to as usize)
-            .and_modify(|stack| stack.append(&mut moved));
+    pub fn locate_at(&self, crate_name: &str, step: usize) -> Vec<Position> {
+        self.at(step)
+            .map(|stacks| stacks.locate(crate_name))
+            .unwrap_or_default()
     }
+}
 
-    let mut answer = "".to_string();
-    for key in 1..=stack_hash_map.len() {
-        answer.push(*stack_hash_map.get(&key).unwrap().last().unwrap());
+impl fmt::Display for Stacks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let widths = self
+            .stacks
+            .iter()
+            .enumerate()
+            .map(|(idx, stack)| {
+                stack
+                    .iter()
+                    .map(|crate_name| crate_name.len() + 2)
+                    .chain([(idx + 1).to_string().len() + 2, 3])
+                    .max()
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+        let height = self.stacks.iter().map(Vec::len).max().unwrap_or(0);
+        for level in (0..height).rev() {
+            let row = self
+                .stacks
+                .iter()
+                .zip(&widths)
+                .map(|(stack, width)| match stack.get(level) {
+                    Some(crate_name) => format!("{:<width$}", format!("[{crate_name}]")),
+                    None => " ".repeat(*width),
+                })
+                .collect::<Vec<_>>();
+            writeln!(f, "{}", row.join(" "))?;
+        }
+        let numbers = widths
+            .iter()
+            .enumerate()
+            .map(|(idx, width)| format!("{:^width$}", idx + 1))
+            .collect::<Vec<_>>();
+        write!(f, "{}", numbers.join(" "))
     }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "move {} from {} to {}", self.amount, self.from, self.to)
+    }
+}
+
+fn number_row(lines: &[&str]) -> Result<usize, ParseError> {
+    lines
+        .iter()
+        .position(|line| is_number_row(line))
+        .ok_or(ParseError::MissingNumberRow)
+}
+
+pub fn parse_drawing(input: &str) -> Result<Stacks, ParseError> {
+    let lines = input.lines().collect::<Vec<_>>();
+    let number_row = number_row(&lines)?;
+    Stacks::from_drawing(&lines[..=number_row])
+}
+
+pub fn parse(input: &str) -> Result<(Stacks, Vec<Instruction>), ParseError> {
+    let lines = input.lines().collect::<Vec<_>>();
+    let number_row = number_row(&lines)?;
+    let instructions = lines
+        .iter()
+        .enumerate()
+        .skip(number_row + 1)
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(idx, line)| {
+            all_consuming(instruction)(line.trim())
+                .map(|(_, instruction)| instruction)
+                .map_err(|_| ParseError::InvalidInstruction {
+                    line: idx + 1,
+                    text: line.trim().to_string(),
+                })
+        })
+        .collect::<Result<_, _>>()?;
+    Ok((Stacks::from_drawing(&lines[..=number_row])?, instructions))
+}
+
+pub fn part_1(input: String) -> Result<String, PuzzleError> {
+    let (stacks, instructions) = parse(&input)?;
+    Ok(stacks
+        .apply(&instructions, &CrateMover9000)?
+        .tops()
+        .into_iter()
+        .flatten()
+        .collect())
+}
 
-    answer
+pub fn part_2(input: String) -> Result<String, PuzzleError> {
+    let (stacks, instructions) = parse(&input)?;
+    Ok(stacks
+        .apply(&instructions, &CrateMover9001)?
+        .tops()
+        .into_iter()
+        .flatten()
+        .collect())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn top_names(stacks: &Stacks) -> Vec<String> {
+        stacks
+            .tops()
+            .into_iter()
+            .map(Option::unwrap_or_default)
+            .collect()
+    }
+
     const INPUT: &str = "    [D]    
-    [N] [C]    
-    [Z] [M] [P]
-     1   2   3 
-    
-    move 1 from 2 to 1
-    move 3 from 1 to 3
-    move 2 from 2 to 1
-    move 1 from 1 to 2";
+[N] [C]    
+[Z] [M] [P]
+ 1   2   3 
+
+move 1 from 2 to 1
+move 3 from 1 to 3
+move 2 from 2 to 1
+move 1 from 1 to 2";
 
     #[test]
     fn top_crates() {
-        assert_eq!(part_1(INPUT.to_string()), "CMZ");
+        assert_eq!(part_1(INPUT.to_string()), Ok("CMZ".to_string()));
     }
 
     #[test]
     fn top_crates_corrected() {
-        assert_eq!(part_1(INPUT.to_string()), "MCD");
+        assert_eq!(part_2(INPUT.to_string()), Ok("MCD".to_string()));
+    }
+
+    #[test]
+    fn limited_lift() {
+        let (stacks, instructions) = parse(INPUT).unwrap();
+        assert_eq!(
+            stacks
+                .clone()
+                .apply(&instructions, &LimitedLift { max: 1 })
+                .unwrap()
+                .tops()
+                .into_iter()
+                .flatten()
+                .collect::<String>(),
+            "CMZ"
+        );
+        assert_eq!(
+            stacks
+                .clone()
+                .apply(&instructions, &LimitedLift { max: 3 })
+                .unwrap()
+                .tops()
+                .into_iter()
+                .flatten()
+                .collect::<String>(),
+            "MCD"
+        );
+        assert_eq!(
+            stacks
+                .apply(&instructions, &LimitedLift { max: 2 })
+                .unwrap()
+                .tops()
+                .into_iter()
+                .flatten()
+                .collect::<String>(),
+            "MCZ"
+        );
+    }
+
+    #[test]
+    fn drawing_round_trip() {
+        let (stacks, instructions) = parse(INPUT).unwrap();
+        let drawing = stacks.to_string();
+        assert_eq!(drawing, INPUT.split("\n\n").next().unwrap());
+        assert_eq!(parse_drawing(&drawing).unwrap(), stacks);
+
+        let mut drawings = vec![];
+        let stacks = stacks
+            .apply_with(&instructions, &CrateMover9000, |_, _, stacks| {
+                drawings.push(stacks.to_string());
+            })
+            .unwrap();
+        assert_eq!(
+            drawings[1],
+            "        [Z]
+        [N]
+    [C] [D]
+    [M] [P]
+ 1   2   3 "
+        );
+        for drawing in drawings {
+            assert_eq!(parse_drawing(&drawing).unwrap().to_string(), drawing);
+        }
+        assert_eq!(
+            stacks.to_string(),
+            "        [Z]
+        [N]
+        [D]
+[C] [M] [P]
+ 1   2   3 "
+        );
+    }
+
+    #[test]
+    fn invalid_instructions() {
+        let (stacks, _) = parse(INPUT).unwrap();
+        let error = |instructions: &[(usize, usize, usize)]| {
+            let instructions = instructions
+                .iter()
+                .map(|(amount, from, to)| Instruction {
+                    amount: *amount,
+                    from: *from,
+                    to: *to,
+                })
+                .collect::<Vec<_>>();
+            let error = stacks
+                .clone()
+                .apply(&instructions, &CrateMover9000)
+                .unwrap_err();
+            (error.step, error.problem)
+        };
+
+        assert_eq!(error(&[(1, 2, 4)]), (1, Problem::UnknownStack(4)));
+        assert_eq!(error(&[(1, 0, 1)]), (1, Problem::UnknownStack(0)));
+        assert_eq!(error(&[(1, 1, 2), (1, 3, 3)]), (2, Problem::SameStack(3)));
+        assert_eq!(
+            error(&[(2, 1, 2), (1, 1, 3)]),
+            (
+                2,
+                Problem::InsufficientCrates {
+                    stack: 1,
+                    available: 0
+                }
+            )
+        );
+
+        let input = INPUT.replace("move 3 from 1 to 3", "move 4 from 1 to 3");
+        assert_eq!(
+            part_1(input).unwrap_err().to_string(),
+            "instruction 2 (move 4 from 1 to 3): stack 1 has 3 crates, cannot move 4"
+        );
+
+        let input = INPUT.replace("move 2 from 2 to 1", "move two from 2 to 1");
+        assert_eq!(
+            parse(&input),
+            Err(ParseError::InvalidInstruction {
+                line: 8,
+                text: "move two from 2 to 1".to_string()
+            })
+        );
+        assert_eq!(
+            part_2(INPUT.replace("to 2", "to 2 now"))
+                .unwrap_err()
+                .to_string(),
+            "line 9: invalid instruction \"move 1 from 1 to 2 now\""
+        );
+        for line in ["move 1 to 2 from 1", "foo 1 bar 1 baz 2", "move 1 from 1"] {
+            assert_eq!(
+                parse(&format!("[A]\n 1   2\n\n{line}")),
+                Err(ParseError::InvalidInstruction {
+                    line: 4,
+                    text: line.to_string()
+                })
+            );
+        }
+        assert_eq!(
+            part_1("[A]\n\nmove 1 from 1 to 2".to_string()),
+            Err(PuzzleError::Parse(ParseError::MissingNumberRow))
+        );
+    }
+
+    #[test]
+    fn time_travel() {
+        let (stacks, instructions) = parse(INPUT).unwrap();
+        let mut history = History::with_interval(stacks.clone(), 2);
+        for instruction in &instructions {
+            history.record(instruction, &CrateMover9000).unwrap();
+        }
+        assert_eq!(history.len(), 4);
+        assert_eq!(top_names(history.current()), ["C", "M", "Z"]);
+        assert_eq!(history.at(0), Some(stacks));
+        assert_eq!(history.at(5), None);
+        assert_eq!(history.top_at(1, 1).as_deref(), Some("D"));
+        assert_eq!(history.top_at(3, 2).as_deref(), Some("Z"));
+        assert_eq!(history.top_at(1, 2), None);
+        assert_eq!(history.locate_at("N", 3), [Position { stack: 3, level: 2 }]);
+
+        let mut drawings = vec![];
+        let (stacks, _) = parse(INPUT).unwrap();
+        stacks
+            .apply_with(&instructions, &CrateMover9000, |_, _, stacks| {
+                drawings.push(stacks.clone());
+            })
+            .unwrap();
+        for (step, drawing) in drawings.iter().enumerate() {
+            assert_eq!(history.at(step + 1).as_ref(), Some(drawing));
+        }
+
+        assert_eq!(history.undo(), Some(instructions[3]));
+        assert_eq!(history.undo(), Some(instructions[2]));
+        assert_eq!(history.current(), &drawings[1]);
+        assert_eq!(history.redo(), Some(instructions[2]));
+        assert_eq!(history.current(), &drawings[2]);
+
+        let instruction = Instruction {
+            amount: 1,
+            from: 3,
+            to: 2,
+        };
+        history.record(&instruction, &CrateMover9000).unwrap();
+        assert_eq!(history.len(), 4);
+        assert_eq!(history.redo(), None);
+        assert_eq!(top_names(history.current()), ["M", "Z", "N"]);
+        assert_eq!(top_names(&history.at(4).unwrap()), ["M", "Z", "N"]);
+        while history.undo().is_some() {}
+        assert_eq!(history.step(), 0);
+        assert_eq!(history.current(), &parse(INPUT).unwrap().0);
+    }
+
+    #[test]
+    fn long_history() {
+        let (stacks, _) = parse(INPUT).unwrap();
+        let instructions = (0..200_000)
+            .map(|idx| Instruction {
+                amount: 1,
+                from: idx % 3 + 1,
+                to: (idx + 1) % 3 + 1,
+            })
+            .collect::<Vec<_>>();
+        let history = History::build(stacks.clone(), &instructions, &CrateMover9001).unwrap();
+        for step in [0, 1, 1023, 1024, 150_001, 200_000] {
+            let expected = stacks
+                .clone()
+                .apply(&instructions[..step], &CrateMover9001)
+                .unwrap();
+            assert_eq!(history.at(step), Some(expected));
+        }
+    }
+
+    #[test]
+    fn wide_drawings() {
+        let input = "[A]                                         [K]
+[B] [C] [D] [E] [F] [G] [H] [I] [J] [XY] [L]
+ 1   2   3   4   5   6   7   8   9   10   11
+
+move 1 from 10 to 1
+move 2 from 11 to 3
+";
+        let (stacks, instructions) = parse(input).unwrap();
+        assert_eq!(
+            stacks.to_string(),
+            "[A]                                      [K] \n\
+             [B] [C] [D] [E] [F] [G] [H] [I] [J] [XY] [L] \n \
+             1   2   3   4   5   6   7   8   9   10   11 "
+        );
+        assert_eq!(parse_drawing(&stacks.to_string()).unwrap(), stacks);
+        assert_eq!(
+            top_names(&stacks),
+            ["A", "C", "D", "E", "F", "G", "H", "I", "J", "XY", "K"]
+        );
+        let stacks = stacks.apply(&instructions, &CrateMover9001).unwrap();
+        assert_eq!(
+            top_names(&stacks),
+            ["XY", "C", "K", "E", "F", "G", "H", "I", "J", "", ""]
+        );
+        assert_eq!(stacks.tops()[9], None);
+        assert_eq!(stacks.tops()[2].as_deref(), Some("K"));
+
+        let (stacks, instructions) =
+            parse("[A]     [C]\n 1   2   3\n\nmove 1 from 1 to 3").unwrap();
+        let stacks = stacks.apply(&instructions, &CrateMover9000).unwrap();
+        assert_eq!(stacks.tops(), [None, None, Some("A".to_string())]);
+
+        assert_eq!(
+            parse_drawing("[A]\n[B] [\n 1   2"),
+            Err(ParseError::UnterminatedCrate { line: 2 })
+        );
+        assert_eq!(
+            parse_drawing("[A] [B [C]\n 1   2"),
+            Err(ParseError::UnterminatedCrate { line: 1 })
+        );
+        assert_eq!(
+            parse_drawing("[A] [B] [C]\n 1   2"),
+            Err(ParseError::OverlappingCrates { line: 1, stack: 2 })
+        );
+        assert_eq!(
+            ParseError::OverlappingCrates { line: 1, stack: 2 }.to_string(),
+            "line 1: more than one crate sits over stack 2"
+        );
+
+        let trimmed = "    [D]\n[NN] [C]\n[Z] [M] [P]\n 1   2   3\n\nmove 1 from 2 to 1";
+        let (stacks, _) = parse(trimmed).unwrap();
+        assert_eq!(top_names(&stacks), ["NN", "D", "P"]);
+        assert_eq!(parse_drawing(&stacks.to_string()).unwrap(), stacks);
     }
 }
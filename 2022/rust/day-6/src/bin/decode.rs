@@ -0,0 +1,15 @@
+use std::io::{stdin, stdout, Write};
+
+use day_6::{Decoder, MarkerKind};
+
+fn main() {
+    let mut stdout = stdout().lock();
+    for marker in Decoder::new(stdin().lock()) {
+        let marker = marker.unwrap();
+        let kind = match marker.kind {
+            MarkerKind::Packet => "packet",
+            MarkerKind::Message => "message",
+        };
+        writeln!(stdout, "{kind} {}", marker.position).unwrap();
+    }
+}
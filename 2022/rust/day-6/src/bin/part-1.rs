@@ -1,8 +1,14 @@
-use std::fs::read_to_string;
+use std::{fs::read_to_string, process::exit};
 
 use day_6::part_1;
 
 fn main() {
     let input = read_to_string("input.txt").unwrap();
-    println!("{}", part_1(input.as_str()));
+    match part_1(input.as_str()) {
+        Ok(position) => println!("{position}"),
+        Err(err) => {
+            eprintln!("{err}");
+            exit(1);
+        }
+    }
 }
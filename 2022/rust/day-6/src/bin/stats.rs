@@ -0,0 +1,23 @@
+use std::fs::read_to_string;
+
+use day_6::{all_markers, first_marker_histogram, longest_distinct_run};
+
+fn main() {
+    let input = read_to_string("input.txt").unwrap();
+    let bytes = input.trim_end().as_bytes();
+
+    let longest = longest_distinct_run(bytes);
+    println!(
+        "longest distinct run: {} at {:?} ({})",
+        longest.len(),
+        longest,
+        String::from_utf8_lossy(&bytes[longest.clone()])
+    );
+    for (size, position) in first_marker_histogram(bytes) {
+        let markers = all_markers(bytes, size).len();
+        match position {
+            Some(position) => println!("{size:>2}: first at {position:>5}, {markers} markers"),
+            None => println!("{size:>2}: none"),
+        }
+    }
+}
@@ -1,33 +1,266 @@
-pub fn part_1(input: &str) -> u32 {
-    let mut tracker = String::new();
-    let mut chars = input.chars().enumerate();
-    while tracker.len() < 4 {
-        let (_, current) = chars.next().unwrap();
-        if !tracker.contains(current) {
-            tracker.push(current);
-        } else {
-            let first_appear = tracker.chars().position(|char| char == current).unwrap();
-            drop(tracker.drain(..=first_appear));
-            tracker.push(current);
+use std::{
+    collections::{HashMap, VecDeque},
+    error::Error,
+    fmt,
+    io::{self, BufReader, Bytes, Read},
+    ops::Range,
+};
+
+pub struct MarkerDetector {
+    size: usize,
+    counts: [usize; 256],
+    duplicates: usize,
+    window: VecDeque<u8>,
+}
+
+impl MarkerDetector {
+    pub fn new(size: usize) -> Self {
+        MarkerDetector {
+            size,
+            counts: [0; 256],
+            duplicates: 0,
+            window: VecDeque::with_capacity(size + 1),
+        }
+    }
+
+    pub fn push(&mut self, byte: u8) -> bool {
+        self.window.push_back(byte);
+        self.counts[byte as usize] += 1;
+        if self.counts[byte as usize] == 2 {
+            self.duplicates += 1;
+        }
+
+        if self.window.len() > self.size {
+            let old = self.window.pop_front().unwrap() as usize;
+            self.counts[old] -= 1;
+            if self.counts[old] == 1 {
+                self.duplicates -= 1;
+            }
+        }
+        self.window.len() == self.size && self.duplicates == 0
+    }
+
+    pub fn reset(&mut self) {
+        self.counts = [0; 256];
+        self.duplicates = 0;
+        self.window.clear();
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarkerKind {
+    Packet,
+    Message,
+}
+
+impl MarkerKind {
+    pub fn size(&self) -> usize {
+        match self {
+            MarkerKind::Packet => 4,
+            MarkerKind::Message => 14,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Marker {
+    pub kind: MarkerKind,
+    pub position: usize,
+}
+
+/// Streams packet and message markers. Each detector starts over after it
+/// reports a marker, so markers of one kind never overlap (7, 11, 15, ...),
+/// unlike [`all_markers`], which reports every matching window (7, 8, 9, ...).
+pub struct Decoder<R: Read> {
+    bytes: Bytes<BufReader<R>>,
+    packet: MarkerDetector,
+    message: MarkerDetector,
+    position: usize,
+    pending: Option<Marker>,
+}
+
+impl<R: Read> Decoder<R> {
+    pub fn new(reader: R) -> Self {
+        Decoder {
+            bytes: BufReader::new(reader).bytes(),
+            packet: MarkerDetector::new(MarkerKind::Packet.size()),
+            message: MarkerDetector::new(MarkerKind::Message.size()),
+            position: 0,
+            pending: None,
+        }
+    }
+}
+
+impl<R: Read> Iterator for Decoder<R> {
+    type Item = io::Result<Marker>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(marker) = self.pending.take() {
+            return Some(Ok(marker));
+        }
+        for byte in self.bytes.by_ref() {
+            let byte = match byte {
+                Ok(byte) => byte,
+                Err(err) => return Some(Err(err)),
+            };
+            self.position += 1;
+            let packet = self.packet.push(byte);
+            let message = self.message.push(byte);
+            if packet {
+                self.packet.reset();
+            }
+            if message {
+                self.message.reset();
+            }
+            let position = self.position;
+            let mut found = [(MarkerKind::Packet, packet), (MarkerKind::Message, message)]
+                .into_iter()
+                .filter_map(|(kind, found)| found.then_some(Marker { kind, position }));
+            if let Some(marker) = found.next() {
+                self.pending = found.next();
+                return Some(Ok(marker));
+            }
+        }
+        None
+    }
+}
+
+pub struct Splitter<R: Read> {
+    bytes: Bytes<BufReader<R>>,
+    detector: MarkerDetector,
+    size: usize,
+    payload: Option<Vec<u8>>,
+}
+
+impl<R: Read> Splitter<R> {
+    pub fn new(reader: R, kind: MarkerKind) -> Self {
+        Splitter {
+            bytes: BufReader::new(reader).bytes(),
+            detector: MarkerDetector::new(kind.size()),
+            size: kind.size(),
+            payload: None,
+        }
+    }
+}
+
+impl<R: Read> Iterator for Splitter<R> {
+    type Item = io::Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for byte in self.bytes.by_ref() {
+            let byte = match byte {
+                Ok(byte) => byte,
+                Err(err) => return Some(Err(err)),
+            };
+            if let Some(payload) = self.payload.as_mut() {
+                payload.push(byte);
+            }
+            if self.detector.push(byte) {
+                self.detector.reset();
+                if let Some(mut payload) = self.payload.replace(vec![]) {
+                    payload.truncate(payload.len() - self.size);
+                    return Some(Ok(payload));
+                }
+            }
+        }
+        self.payload.take().map(Ok)
+    }
+}
+
+pub fn find_marker(bytes: &[u8], size: usize) -> Option<usize> {
+    if size == 0 {
+        return Some(0);
+    }
+    let mut detector = MarkerDetector::new(size);
+    bytes
+        .iter()
+        .position(|byte| detector.push(*byte))
+        .map(|idx| idx + 1)
+}
+
+/// Reports the end of every window of `size` distinct bytes, including
+/// overlapping ones. See [`Decoder`] for non-overlapping markers.
+pub fn all_markers(bytes: &[u8], size: usize) -> Vec<usize> {
+    let mut detector = MarkerDetector::new(size);
+    bytes
+        .iter()
+        .enumerate()
+        .filter(|(_, byte)| detector.push(**byte))
+        .map(|(idx, _)| idx + 1)
+        .collect()
+}
+
+pub fn longest_distinct_run(bytes: &[u8]) -> Range<usize> {
+    let (mut low, mut high) = (0, bytes.len().min(256));
+    while low < high {
+        let size = (low + high).div_ceil(2);
+        match find_marker(bytes, size) {
+            Some(_) => low = size,
+            None => high = size - 1,
         }
     }
-    chars.next().unwrap().0 as u32
+    let end = find_marker(bytes, low).unwrap();
+    end - low..end
 }
 
-pub fn part_2(input: &str) -> u32 {
-    let mut tracker = String::new();
-    let mut chars = input.chars().enumerate();
-    while tracker.len() < 14 {
-        let (_, current) = chars.next().unwrap();
-        if !tracker.contains(current) {
-            tracker.push(current);
-        } else {
-            let first_appear = tracker.chars().position(|char| char == current).unwrap();
-            drop(tracker.drain(..=first_appear));
-            tracker.push(current);
+pub fn first_marker_histogram(bytes: &[u8]) -> Vec<(usize, Option<usize>)> {
+    (1..=26)
+        .map(|size| (size, find_marker(bytes, size)))
+        .collect()
+}
+
+pub fn find_char_marker(input: &str, size: usize) -> Option<usize> {
+    if input.is_ascii() || size == 0 {
+        return find_marker(input.as_bytes(), size);
+    }
+    let chars = input.chars().collect::<Vec<_>>();
+    let mut counts = HashMap::new();
+    let mut duplicates = 0;
+    for (idx, char) in chars.iter().enumerate() {
+        let count = counts.entry(*char).or_insert(0);
+        *count += 1;
+        if *count == 2 {
+            duplicates += 1;
         }
+        if idx >= size {
+            let count = counts.get_mut(&chars[idx - size]).unwrap();
+            *count -= 1;
+            if *count == 1 {
+                duplicates -= 1;
+            }
+        }
+        if idx + 1 >= size && duplicates == 0 {
+            return Some(idx + 1);
+        }
+    }
+    None
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MissingMarker {
+    pub size: usize,
+}
+
+impl fmt::Display for MissingMarker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "no run of {} distinct characters found", self.size)
     }
-    chars.next().unwrap().0 as u32
+}
+
+impl Error for MissingMarker {}
+
+fn marker_position(input: &str, size: usize) -> Result<u32, MissingMarker> {
+    find_char_marker(input, size)
+        .map(|position| position as u32)
+        .ok_or(MissingMarker { size })
+}
+
+pub fn part_1(input: &str) -> Result<u32, MissingMarker> {
+    marker_position(input, MarkerKind::Packet.size())
+}
+
+pub fn part_2(input: &str) -> Result<u32, MissingMarker> {
+    marker_position(input, MarkerKind::Message.size())
 }
 
 #[cfg(test)]
@@ -38,10 +271,89 @@ mod tests {
 
     #[test]
     fn processed_count() {
-        assert_eq!(part_1(INPUT), 11);
+        assert_eq!(part_1(INPUT), Ok(11));
     }
     #[test]
     fn message_processed_count() {
-        assert_eq!(part_2(INPUT), 26);
+        assert_eq!(part_2(INPUT), Ok(26));
+    }
+
+    #[test]
+    fn marker_sizes() {
+        assert_eq!(find_marker(b"mjqjpqmgbljsphdztnvjfqwrcgsmlb", 4), Some(7));
+        assert_eq!(find_marker(b"mjqjpqmgbljsphdztnvjfqwrcgsmlb", 14), Some(19));
+        assert_eq!(find_marker(b"aab", 1), Some(1));
+        assert_eq!(find_marker(b"aab", 2), Some(3));
+        assert_eq!(find_marker(b"aaaa", 2), None);
+        assert_eq!(find_marker(b"", 0), Some(0));
+    }
+
+    #[test]
+    fn non_ascii_markers() {
+        assert_eq!(part_1("αβγδεζ"), Ok(4));
+        assert_eq!(find_char_marker("aαaβγ", 4), Some(5));
+        assert_eq!(find_char_marker("ααββ", 2), Some(3));
+        assert_eq!(part_1("ααααβ"), Err(MissingMarker { size: 4 }));
+        assert_eq!(
+            part_2("abc").unwrap_err().to_string(),
+            "no run of 14 distinct characters found"
+        );
+    }
+
+    #[test]
+    fn streamed_markers() {
+        let input = "mjqjpqmgbljsphdztnvjfqwrcgsmlb";
+        let markers = Decoder::new(input.as_bytes())
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            markers[0],
+            Marker {
+                kind: MarkerKind::Packet,
+                position: 7
+            }
+        );
+        assert_eq!(
+            markers
+                .iter()
+                .find(|marker| marker.kind == MarkerKind::Message)
+                .unwrap()
+                .position,
+            19
+        );
+        assert_eq!(
+            markers
+                .iter()
+                .filter(|marker| marker.kind == MarkerKind::Packet)
+                .map(|marker| marker.position)
+                .collect::<Vec<_>>(),
+            vec![7, 11, 15, 19, 23, 27]
+        );
+    }
+
+    #[test]
+    fn split_packets() {
+        let payloads = Splitter::new("aabcdxyzzefghq".as_bytes(), MarkerKind::Packet)
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(payloads, vec![b"xyz".to_vec(), b"hq".to_vec()]);
+    }
+
+    #[test]
+    fn marker_statistics() {
+        let input = b"mjqjpqmgbljsphdztnvjfqwrcgsmlb";
+        assert_eq!(all_markers(input, 4)[..3], [7, 8, 9]);
+        assert_eq!(all_markers(b"abab", 2), vec![2, 3, 4]);
+        assert_eq!(all_markers(b"aaaa", 2), vec![]);
+
+        assert_eq!(longest_distinct_run(input), 12..30);
+        assert_eq!(longest_distinct_run(b""), 0..0);
+        assert_eq!(longest_distinct_run(b"aaaa"), 0..1);
+
+        let histogram = first_marker_histogram(input);
+        assert_eq!(histogram.len(), 26);
+        assert_eq!(histogram[3], (4, Some(7)));
+        assert_eq!(histogram[13], (14, Some(19)));
+        assert_eq!(histogram[25], (26, None));
     }
 }
@@ -0,0 +1,35 @@
+use std::{env, fs, process::exit};
+
+use day_8::{render_ansi, render_pgm, setup_grid, Layer, Sight, SightRules};
+
+fn main() {
+    let input = fs::read_to_string("input.txt").unwrap();
+    let grid = match setup_grid(&input) {
+        Ok(grid) => grid,
+        Err(err) => {
+            eprintln!("{err}");
+            exit(1);
+        }
+    };
+    let mut layer = Layer::Visibility;
+    let mut rules = SightRules::default();
+    let mut path = None;
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "visibility" => layer = Layer::Visibility,
+            "scenic" => layer = Layer::Scenic,
+            "--diagonal" => rules.diagonal = true,
+            "--all" => rules.visible_from = Sight::All,
+            _ => path = Some(arg),
+        }
+    }
+    match path {
+        Some(path) => {
+            if let Err(err) = fs::write(&path, render_pgm(&grid, layer, &rules)) {
+                eprintln!("{path}: {err}");
+                exit(1);
+            }
+        }
+        None => print!("{}", render_ansi(&grid, layer, &rules)),
+    }
+}
@@ -0,0 +1,25 @@
+use std::{env, fs::read_to_string, process::exit};
+
+use day_8::{setup_grid, top_spots, SightRules};
+
+fn main() {
+    let input = read_to_string("input.txt").unwrap();
+    let grid = match setup_grid(&input) {
+        Ok(grid) => grid,
+        Err(err) => {
+            eprintln!("{err}");
+            exit(1);
+        }
+    };
+    let mut k = 5;
+    let mut rules = SightRules::default();
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--diagonal" => rules.diagonal = true,
+            _ => k = arg.parse().unwrap_or(k),
+        }
+    }
+    for spot in top_spots(&grid, k, &rules) {
+        println!("row {} col {}: {}", spot.row, spot.col, spot.score);
+    }
+}
@@ -1,95 +1,395 @@
+use std::{error::Error, fmt, iter};
+
 use grid::Grid;
 
-fn setup_grid(input: String) -> Grid<u32> {
-    Grid::from_vec(
-        input
-            .lines()
-            .flat_map(|row| {
-                row.chars()
-                    .map(|c| c.to_digit(10).unwrap())
-                    .collect::<Vec<u32>>()
-            })
-            .collect::<Vec<u32>>(),
-        input.lines().next().unwrap().len(),
-    )
+pub const UP: (isize, isize) = (-1, 0);
+pub const DOWN: (isize, isize) = (1, 0);
+pub const LEFT: (isize, isize) = (0, -1);
+pub const RIGHT: (isize, isize) = (0, 1);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GridError {
+    Empty,
+    UnevenRow {
+        row: usize,
+        expected: usize,
+        found: usize,
+    },
+    InvalidHeight {
+        row: usize,
+        col: usize,
+        height: String,
+    },
 }
 
-fn hidden_tree<'a>(
-    tree: &&u32,
-    mut up: impl Iterator<Item = &'a u32>,
-    mut down: impl Iterator<Item = &'a u32>,
-    mut left: impl Iterator<Item = &'a u32>,
-    mut right: impl Iterator<Item = &'a u32>,
-) -> bool {
-    left.any(|left| left >= tree)
-        && right.any(|right| right >= tree)
-        && up.any(|up| up >= tree)
-        && down.any(|down| down >= tree)
+impl fmt::Display for GridError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GridError::Empty => write!(f, "grid is empty"),
+            GridError::UnevenRow {
+                row,
+                expected,
+                found,
+            } => write!(f, "row {row} has {found} trees, expected {expected}"),
+            GridError::InvalidHeight { row, col, height } => {
+                write!(f, "invalid height {height:?} at row {row}, column {col}")
+            }
+        }
+    }
 }
 
-pub fn part_1(input: String) -> usize {
-    let grid = setup_grid(input);
-    let (rows, cols) = grid.size();
-    let mut visible: usize = rows * cols;
+impl Error for GridError {}
 
-    for row in 1..(rows - 1) {
-        for col in 1..(cols - 1) {
-            let tree = grid.get(row, col).unwrap();
+fn is_delimiter(c: char) -> bool {
+    c == ',' || c.is_whitespace()
+}
 
-            let left = grid.iter_row(row).take(col);
-            let right = grid.iter_row(row).skip(col + 1);
-            let up = grid.iter_col(col).take(row);
-            let down = grid.iter_col(col).skip(row + 1);
+pub fn setup_grid(input: &str) -> Result<Grid<u32>, GridError> {
+    let rows = input
+        .lines()
+        .map(str::trim)
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty())
+        .collect::<Vec<_>>();
+    let delimited = rows.iter().any(|(_, line)| line.contains(is_delimiter));
 
-            if hidden_tree(&tree, up, down, left, right) {
-                visible -= 1;
-            }
+    let mut cols = None;
+    let mut heights = vec![];
+    for (row, line) in rows {
+        let cells = if delimited {
+            line.split(is_delimiter)
+                .filter(|height| !height.is_empty())
+                .collect::<Vec<_>>()
+        } else {
+            line.char_indices()
+                .map(|(idx, c)| &line[idx..idx + c.len_utf8()])
+                .collect()
+        };
+        let expected = *cols.get_or_insert(cells.len());
+        if cells.len() != expected {
+            return Err(GridError::UnevenRow {
+                row,
+                expected,
+                found: cells.len(),
+            });
+        }
+        for (col, height) in cells.into_iter().enumerate() {
+            heights.push(height.parse().map_err(|_| GridError::InvalidHeight {
+                row,
+                col,
+                height: height.to_string(),
+            })?);
         }
     }
+    Ok(Grid::from_vec(heights, cols.ok_or(GridError::Empty)?))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sight {
+    Any,
+    All,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct SightRules {
+    pub diagonal: bool,
+    pub visible_from: Sight,
+}
+
+impl Default for SightRules {
+    fn default() -> Self {
+        SightRules {
+            diagonal: false,
+            visible_from: Sight::Any,
+        }
+    }
+}
+
+impl SightRules {
+    fn offsets(&self) -> Vec<(isize, isize)> {
+        let mut offsets = vec![UP, DOWN, LEFT, RIGHT];
+        if self.diagonal {
+            offsets.extend([(-1, -1), (-1, 1), (1, -1), (1, 1)]);
+        }
+        offsets
+    }
+}
+
+fn neighbour(
+    grid: &Grid<u32>,
+    (row, col): (usize, usize),
+    (row_offset, col_offset): (isize, isize),
+) -> Option<(usize, usize)> {
+    let row = row.checked_add_signed(row_offset)?;
+    let col = col.checked_add_signed(col_offset)?;
+    (row < grid.rows() && col < grid.cols()).then_some((row, col))
+}
 
-    visible
+fn ray(
+    grid: &Grid<u32>,
+    row: usize,
+    col: usize,
+    offset: (isize, isize),
+) -> impl Iterator<Item = &u32> {
+    iter::successors(Some((row, col)), move |cell| neighbour(grid, *cell, offset))
+        .skip(1)
+        .map(|(row, col)| &grid[row][col])
+}
+
+fn hidden_tree<'a, I: Iterator<Item = &'a u32>>(
+    tree: &u32,
+    mut rays: impl Iterator<Item = I>,
+    visible_from: Sight,
+) -> bool {
+    let blocked = |mut ray: I| ray.any(|height| height >= tree);
+    match visible_from {
+        Sight::Any => rays.all(blocked),
+        Sight::All => rays.any(blocked),
+    }
 }
 
 fn visible_trees<'a>(tree: &u32, trees: impl Iterator<Item = &'a u32>) -> usize {
-    let mut last_idx: usize = 0;
+    let mut count = 0;
 
-    for (idx, height) in trees.enumerate() {
+    for height in trees {
+        count += 1;
         if height >= tree {
-            return idx + 1;
+            break;
         }
-        last_idx = idx;
     }
 
-    last_idx + 1
+    count
 }
 
-pub fn part_2(input: String) -> usize {
-    let grid = setup_grid(input);
+pub fn count_visible(input: &str, rules: &SightRules) -> Result<usize, GridError> {
+    let grid = setup_grid(input)?;
     let (rows, cols) = grid.size();
-    let mut score: usize = 0;
+    let offsets = rules.offsets();
 
-    for row in 1..(rows - 1) {
-        for col in 1..(cols - 1) {
-            let tree = grid.get(row, col).unwrap();
+    Ok((0..rows)
+        .flat_map(|row| (0..cols).map(move |col| (row, col)))
+        .filter(|(row, col)| {
+            let rays = offsets.iter().map(|offset| ray(&grid, *row, *col, *offset));
+            !hidden_tree(&grid[*row][*col], rays, rules.visible_from)
+        })
+        .count())
+}
 
-            let up = grid.iter_col(col).take(row).rev();
-            let down = grid.iter_col(col).skip(row + 1);
-            let left = grid.iter_row(row).take(col).rev();
-            let right = grid.iter_row(row).skip(col + 1);
+pub fn best_view(input: &str, rules: &SightRules) -> Result<usize, GridError> {
+    let grid = setup_grid(input)?;
+    let (rows, cols) = grid.size();
+    let offsets = rules.offsets();
 
-            let up_score = visible_trees(&tree, up);
-            let down_score = visible_trees(&tree, down);
-            let left_score = visible_trees(&tree, left);
-            let right_score = visible_trees(&tree, right);
-            let total_score = up_score * down_score * left_score * right_score;
+    Ok((0..rows)
+        .flat_map(|row| (0..cols).map(move |col| (row, col)))
+        .map(|(row, col)| {
+            offsets
+                .iter()
+                .map(|offset| visible_trees(&grid[row][col], ray(&grid, row, col, *offset)))
+                .fold(1, usize::saturating_mul)
+        })
+        .max()
+        .unwrap_or(0))
+}
 
-            if total_score > score {
-                score = total_score
-            }
+pub fn part_1_naive(input: String) -> Result<usize, GridError> {
+    count_visible(&input, &SightRules::default())
+}
+
+pub fn part_2_naive(input: String) -> Result<usize, GridError> {
+    best_view(&input, &SightRules::default())
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Views {
+    distances: Vec<((isize, isize), usize)>,
+}
+
+impl Views {
+    pub fn toward(&self, offset: (isize, isize)) -> Option<usize> {
+        self.distances
+            .iter()
+            .find(|(direction, _)| *direction == offset)
+            .map(|(_, distance)| *distance)
+    }
+
+    pub fn score(&self) -> usize {
+        self.distances
+            .iter()
+            .map(|(_, distance)| *distance)
+            .fold(1, usize::saturating_mul)
+    }
+}
+
+pub struct Survey {
+    pub visible: Grid<bool>,
+    pub views: Grid<Views>,
+}
+
+fn sweep(
+    grid: &Grid<u32>,
+    line: impl Iterator<Item = (usize, usize)>,
+    mut record: impl FnMut(usize, usize, bool, usize),
+) {
+    let mut tallest = None;
+    let mut blockers: Vec<(usize, u32)> = vec![];
+    for (idx, (row, col)) in line.enumerate() {
+        let height = grid[row][col];
+        while blockers
+            .last()
+            .is_some_and(|(_, blocker)| *blocker < height)
+        {
+            blockers.pop();
         }
+        let distance = blockers.last().map_or(idx, |(blocker, _)| idx - blocker);
+        blockers.push((idx, height));
+        record(
+            row,
+            col,
+            tallest.is_none_or(|tallest| height > tallest),
+            distance,
+        );
+        tallest = tallest.max(Some(height));
     }
+}
+
+pub fn survey(grid: &Grid<u32>, rules: &SightRules) -> Survey {
+    let (rows, cols) = grid.size();
+    let mut visible = Grid::init(rows, cols, rules.visible_from == Sight::All);
+    let mut views: Grid<Views> = Grid::new(rows, cols);
 
-    score
+    for offset in rules.offsets() {
+        let backwards = (-offset.0, -offset.1);
+        let starts = (0..rows)
+            .flat_map(|row| (0..cols).map(move |col| (row, col)))
+            .filter(|cell| neighbour(grid, *cell, offset).is_none());
+        for start in starts {
+            let line = iter::successors(Some(start), |cell| neighbour(grid, *cell, backwards));
+            sweep(grid, line, |row, col, seen, distance| {
+                match rules.visible_from {
+                    Sight::Any => visible[row][col] |= seen,
+                    Sight::All => visible[row][col] &= seen,
+                }
+                views[row][col].distances.push((offset, distance));
+            });
+        }
+    }
+
+    Survey { visible, views }
+}
+
+pub fn part_1(input: String) -> Result<usize, GridError> {
+    Ok(survey(&setup_grid(&input)?, &SightRules::default())
+        .visible
+        .iter()
+        .filter(|visible| **visible)
+        .count())
+}
+
+pub fn part_2(input: String) -> Result<usize, GridError> {
+    Ok(survey(&setup_grid(&input)?, &SightRules::default())
+        .views
+        .iter()
+        .map(Views::score)
+        .max()
+        .unwrap_or(0))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Spot {
+    pub row: usize,
+    pub col: usize,
+    pub score: usize,
+}
+
+pub fn scenic_scores(grid: &Grid<u32>, rules: &SightRules) -> Vec<Spot> {
+    let cols = grid.cols();
+    survey(grid, rules)
+        .views
+        .iter()
+        .enumerate()
+        .map(|(idx, views)| Spot {
+            row: idx / cols,
+            col: idx % cols,
+            score: views.score(),
+        })
+        .collect()
+}
+
+pub fn top_spots(grid: &Grid<u32>, k: usize, rules: &SightRules) -> Vec<Spot> {
+    let mut spots = scenic_scores(grid, rules);
+    spots.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then((a.row, a.col).cmp(&(b.row, b.col)))
+    });
+    spots.truncate(k);
+    spots
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layer {
+    Visibility,
+    Scenic,
+}
+
+fn shades(grid: &Grid<u32>, layer: Layer, rules: &SightRules, levels: usize) -> Vec<usize> {
+    let survey = survey(grid, rules);
+    match layer {
+        Layer::Visibility => survey
+            .visible
+            .iter()
+            .map(|visible| if *visible { levels } else { 0 })
+            .collect(),
+        Layer::Scenic => {
+            let max = survey.views.iter().map(Views::score).max().unwrap_or(0);
+            survey
+                .views
+                .iter()
+                .map(|views| {
+                    (views.score() as u128 * levels as u128)
+                        .checked_div(max as u128)
+                        .unwrap_or(0) as usize
+                })
+                .collect()
+        }
+    }
+}
+
+pub fn render_ansi(grid: &Grid<u32>, layer: Layer, rules: &SightRules) -> String {
+    let shades = shades(grid, layer, rules, 23);
+    let width = grid
+        .iter()
+        .map(|height| height.to_string().len())
+        .max()
+        .unwrap_or(1);
+    let mut output = String::new();
+    for (idx, height) in grid.iter().enumerate() {
+        if width > 1 && idx % grid.cols() > 0 {
+            output.push(' ');
+        }
+        let color = match layer {
+            Layer::Visibility if shades[idx] > 0 => "32".to_string(),
+            Layer::Visibility => "90".to_string(),
+            Layer::Scenic => format!("97;48;5;{}", 232 + shades[idx]),
+        };
+        output.push_str(&format!("\x1b[{color}m{height:>width$}"));
+        if (idx + 1) % grid.cols() == 0 {
+            output.push_str("\x1b[0m\n");
+        }
+    }
+    output
+}
+
+pub fn render_pgm(grid: &Grid<u32>, layer: Layer, rules: &SightRules) -> String {
+    let shades = shades(grid, layer, rules, 255);
+    let mut output = format!("P2\n{} {}\n255\n", grid.cols(), grid.rows());
+    for row in shades.chunks(grid.cols()) {
+        let row = row.iter().map(usize::to_string).collect::<Vec<_>>();
+        output.push_str(&row.join(" "));
+        output.push('\n');
+    }
+    output
 }
 
 #[cfg(test)]
@@ -104,11 +404,218 @@ mod tests {
 
     #[test]
     fn visible_trees() {
-        assert_eq!(part_1(INPUT.to_string()), 21);
+        assert_eq!(part_1(INPUT.to_string()), Ok(21));
     }
 
     #[test]
     fn best_tree_view_score() {
-        assert_eq!(part_2(INPUT.to_string()), 8);
+        assert_eq!(part_2(INPUT.to_string()), Ok(8));
+    }
+
+    #[test]
+    fn survey_grids() {
+        let survey = survey(&setup_grid(INPUT).unwrap(), &SightRules::default());
+        let hidden = survey
+            .visible
+            .iter()
+            .enumerate()
+            .filter(|(_, visible)| !**visible)
+            .map(|(idx, _)| (idx / 5, idx % 5))
+            .collect::<Vec<_>>();
+        assert_eq!(hidden, [(1, 3), (2, 2), (3, 1), (3, 3)]);
+        let distances = |views: &Views| [UP, DOWN, LEFT, RIGHT].map(|offset| views.toward(offset));
+        assert_eq!(
+            distances(&survey.views[3][2]),
+            [Some(2), Some(1), Some(2), Some(2)]
+        );
+        assert_eq!(
+            distances(&survey.views[0][0]),
+            [Some(0), Some(2), Some(0), Some(2)]
+        );
+        assert_eq!(survey.views[0][0].toward((1, 1)), None);
+        assert_eq!(survey.views[0][0].score(), 0);
+    }
+
+    #[test]
+    fn best_spots() {
+        let grid = setup_grid(INPUT).unwrap();
+        let rules = SightRules::default();
+        let scores = scenic_scores(&grid, &rules);
+        assert_eq!(scores.len(), 25);
+        assert_eq!(
+            scores[7],
+            Spot {
+                row: 1,
+                col: 2,
+                score: 4
+            }
+        );
+        assert_eq!(
+            top_spots(&grid, 2, &rules),
+            [
+                Spot {
+                    row: 3,
+                    col: 2,
+                    score: 8
+                },
+                Spot {
+                    row: 2,
+                    col: 1,
+                    score: 6
+                }
+            ]
+        );
+        assert_eq!(top_spots(&grid, 100, &rules).len(), 25);
+    }
+
+    #[test]
+    fn rendered_maps() {
+        let grid = setup_grid("393\n959\n393").unwrap();
+        let rules = SightRules::default();
+        assert_eq!(
+            render_pgm(&grid, Layer::Visibility, &rules),
+            "P2\n3 3\n255\n255 255 255\n255 0 255\n255 255 255\n"
+        );
+        assert_eq!(
+            render_pgm(&grid, Layer::Scenic, &rules),
+            "P2\n3 3\n255\n0 0 0\n0 255 0\n0 0 0\n"
+        );
+        assert_eq!(
+            render_ansi(&setup_grid("35\n12").unwrap(), Layer::Visibility, &rules),
+            "\x1b[32m3\x1b[32m5\x1b[0m\n\x1b[32m1\x1b[32m2\x1b[0m\n"
+        );
+        let plain = |input| {
+            render_ansi(&setup_grid(input).unwrap(), Layer::Visibility, &rules)
+                .replace("\x1b[32m", "")
+                .replace("\x1b[90m", "")
+                .replace("\x1b[0m", "")
+        };
+        assert_eq!(plain("10,2\n3,4"), "10  2\n 3  4\n");
+        assert_ne!(plain("10,2\n3,4"), plain("1,02\n3,4"));
+        assert!(render_ansi(&grid, Layer::Scenic, &rules).starts_with("\x1b[97;48;5;232m3"));
+        assert!(render_ansi(&grid, Layer::Scenic, &rules).contains("\x1b[97;48;5;255m5"));
+    }
+
+    #[test]
+    fn sight_rules() {
+        let delimited = INPUT
+            .lines()
+            .map(|row| row.chars().map(String::from).collect::<Vec<_>>().join(", "))
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_eq!(setup_grid(&delimited), setup_grid(INPUT));
+        assert_eq!(
+            setup_grid("30373 \n25512\n65332\n"),
+            setup_grid("30373\n25512\n65332")
+        );
+        assert_eq!(setup_grid("\n \n"), Err(GridError::Empty));
+        assert_eq!(
+            setup_grid("303\n25512"),
+            Err(GridError::UnevenRow {
+                row: 1,
+                expected: 3,
+                found: 5
+            })
+        );
+        assert_eq!(
+            setup_grid("10 20\n7 30 12"),
+            Err(GridError::UnevenRow {
+                row: 1,
+                expected: 2,
+                found: 3
+            })
+        );
+        assert_eq!(
+            setup_grid("303\n2x5").unwrap_err().to_string(),
+            "invalid height \"x\" at row 1, column 1"
+        );
+
+        let all = SightRules {
+            visible_from: Sight::All,
+            ..SightRules::default()
+        };
+        let diagonal = SightRules {
+            diagonal: true,
+            ..SightRules::default()
+        };
+        let input = "10 20 5\n7 30 12\n1  2  3\n";
+        assert_eq!(count_visible(input, &SightRules::default()), Ok(9));
+        assert_eq!(count_visible(input, &all), Ok(1));
+        assert_eq!(best_view(input, &diagonal), Ok(1));
+
+        let input = "11111\n12221\n12921\n12221\n11111";
+        assert_eq!(count_visible(input, &SightRules::default()), Ok(25));
+        assert_eq!(count_visible(input, &all), Ok(1));
+        assert_eq!(count_visible(input, &diagonal), Ok(25));
+        assert_eq!(best_view(input, &SightRules::default()), Ok(16));
+        assert_eq!(best_view(input, &diagonal), Ok(256));
+        let both = SightRules {
+            diagonal: true,
+            visible_from: Sight::All,
+        };
+        assert_eq!(count_visible(input, &both), Ok(1));
+    }
+
+    #[test]
+    fn saturated_scores() {
+        let mut rows = vec!["1".repeat(601); 601];
+        rows[300].replace_range(300..301, "2");
+        let grid = setup_grid(&rows.join("\n")).unwrap();
+        let rules = SightRules {
+            diagonal: true,
+            ..SightRules::default()
+        };
+        assert_eq!(
+            top_spots(&grid, 1, &rules),
+            [Spot {
+                row: 300,
+                col: 300,
+                score: usize::MAX
+            }]
+        );
+        let pgm = render_pgm(&grid, Layer::Scenic, &rules);
+        assert_eq!(
+            pgm.lines().nth(303).unwrap().split(' ').nth(300),
+            Some("255")
+        );
+    }
+
+    #[test]
+    fn matches_naive_scan() {
+        let mut seed: u64 = 8;
+        for (rows, cols) in [(1, 1), (2, 7), (9, 3), (30, 30)] {
+            let input = (0..rows)
+                .map(|_| {
+                    (0..cols)
+                        .map(|_| {
+                            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+                            char::from_digit((seed >> 33) as u32 % 10, 10).unwrap()
+                        })
+                        .collect::<String>()
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            assert_eq!(part_1(input.clone()), part_1_naive(input.clone()));
+            assert_eq!(part_2(input.clone()), part_2_naive(input.clone()));
+
+            let grid = setup_grid(&input).unwrap();
+            for (diagonal, visible_from) in
+                [(false, Sight::All), (true, Sight::Any), (true, Sight::All)]
+            {
+                let rules = SightRules {
+                    diagonal,
+                    visible_from,
+                };
+                let survey = survey(&grid, &rules);
+                assert_eq!(
+                    Ok(survey.visible.iter().filter(|visible| **visible).count()),
+                    count_visible(&input, &rules)
+                );
+                assert_eq!(
+                    Ok(survey.views.iter().map(Views::score).max().unwrap()),
+                    best_view(&input, &rules)
+                );
+            }
+        }
     }
 }